@@ -1 +1,2 @@
 pub mod mutable;
+pub mod scanner;
@@ -0,0 +1,208 @@
+use arrow::bitmap::Bitmap;
+
+/// A run of consecutive bits with the same value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BitRun {
+    /// Index of the first bit of the run.
+    pub start: usize,
+    /// Number of bits in the run.
+    pub len: usize,
+    /// Whether the bits in this run are set.
+    pub is_set: bool,
+    /// Number of set bits before `start`.
+    pub set_before: usize,
+}
+
+/// Scans a bitmap a word at a time and yields the runs of set and unset bits.
+///
+/// Run lengths are found with `trailing_ones` on 64 bit words instead of
+/// testing the bits one by one, which makes skipping long null (or long valid)
+/// regions of a validity mask cheap.
+#[derive(Debug, Clone)]
+pub struct BitmapScanner<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    len: usize,
+    pos: usize,
+    set_before: usize,
+}
+
+impl<'a> BitmapScanner<'a> {
+    /// Scan `len` bits starting at bit `offset` of `bytes`.
+    pub fn new(bytes: &'a [u8], offset: usize, len: usize) -> Self {
+        assert!(offset + len <= bytes.len() * 8);
+        Self {
+            bytes,
+            offset,
+            len,
+            pos: 0,
+            set_before: 0,
+        }
+    }
+
+    pub fn from_bitmap(bitmap: &'a Bitmap) -> Self {
+        let (bytes, offset, len) = bitmap.as_slice();
+        Self::new(bytes, offset, len)
+    }
+
+    /// Load the (at most) 64 bits starting at bit `i` relative to the scanned region.
+    /// Bits beyond the end of the region are zero.
+    #[inline]
+    fn load_word(&self, i: usize) -> u64 {
+        let bit = self.offset + i;
+        let byte_start = bit / 8;
+        let shift = bit % 8;
+        let byte_end = std::cmp::min(byte_start + 9, self.bytes.len());
+
+        let mut buf = [0u8; 16];
+        buf[..byte_end - byte_start].copy_from_slice(&self.bytes[byte_start..byte_end]);
+        let word = (u128::from_le_bytes(buf) >> shift) as u64;
+
+        let remaining = self.len - i;
+        if remaining < 64 {
+            word & ((1u64 << remaining) - 1)
+        } else {
+            word
+        }
+    }
+
+    /// Length of the run of bits equal to `is_set` starting at `i`.
+    #[inline]
+    fn run_len(&self, i: usize, is_set: bool) -> usize {
+        let mut pos = i;
+        while pos < self.len {
+            let word = self.load_word(pos);
+            let word = if is_set { word } else { !word };
+            let ones = word.trailing_ones() as usize;
+            pos += ones;
+            if ones < 64 {
+                break;
+            }
+        }
+        std::cmp::min(pos, self.len) - i
+    }
+
+    /// Number of set bits in `[0, i)` of the scanned region.
+    pub fn count_ones_before(&self, i: usize) -> usize {
+        assert!(i <= self.len);
+        let mut count = 0;
+        let mut pos = 0;
+        while pos < i {
+            let word = self.load_word(pos);
+            let n = std::cmp::min(64, i - pos);
+            let mask = if n == 64 { u64::MAX } else { (1u64 << n) - 1 };
+            count += (word & mask).count_ones() as usize;
+            pos += n;
+        }
+        count
+    }
+
+    /// Index of the first set bit at or after `i`, or `None` if there is none.
+    pub fn next_set_bit(&self, i: usize) -> Option<usize> {
+        if i >= self.len {
+            return None;
+        }
+        let next = i + self.run_len(i, false);
+        (next < self.len).then_some(next)
+    }
+}
+
+impl<'a> Iterator for BitmapScanner<'a> {
+    type Item = BitRun;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.len {
+            return None;
+        }
+        let start = self.pos;
+        let is_set = self.load_word(start) & 1 == 1;
+        let len = self.run_len(start, is_set);
+        let run = BitRun {
+            start,
+            len,
+            is_set,
+            set_before: self.set_before,
+        };
+        self.pos += len;
+        if is_set {
+            self.set_before += len;
+        }
+        Some(run)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::bitmap::utils::get_bit;
+    use arrow::bitmap::MutableBitmap;
+
+    use super::*;
+
+    fn random_bytes(n_bytes: usize, seed: u64, density: u64) -> Vec<u8> {
+        // xorshift, good enough to get irregular run lengths
+        let mut state = seed;
+        let mut bitmap = MutableBitmap::with_capacity(n_bytes * 8);
+        for _ in 0..n_bytes * 8 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            bitmap.push(state % 100 < density);
+        }
+        bitmap.as_slice().to_vec()
+    }
+
+    fn naive_runs(bits: &[bool]) -> Vec<BitRun> {
+        let mut runs: Vec<BitRun> = vec![];
+        let mut set_before = 0;
+        for (i, &bit) in bits.iter().enumerate() {
+            match runs.last_mut() {
+                Some(run) if run.is_set == bit => run.len += 1,
+                _ => runs.push(BitRun {
+                    start: i,
+                    len: 1,
+                    is_set: bit,
+                    set_before,
+                }),
+            }
+            if bit {
+                set_before += 1;
+            }
+        }
+        runs
+    }
+
+    #[test]
+    fn test_bitmap_scanner_vs_naive() {
+        for (seed, density) in [(1, 50), (7, 5), (42, 95), (1337, 100), (99, 0)] {
+            let bytes = random_bytes(130, seed, density);
+            for len in [0, 1, 7, 63, 64, 65, 200, 1000] {
+                for offset in [0, 1, 5, 9] {
+                    let bits = (offset..offset + len)
+                        .map(|i| get_bit(&bytes, i))
+                        .collect::<Vec<_>>();
+
+                    let runs = BitmapScanner::new(&bytes, offset, len).collect::<Vec<_>>();
+                    assert_eq!(runs, naive_runs(&bits));
+
+                    let scanner = BitmapScanner::new(&bytes, offset, len);
+                    for i in 0..=len {
+                        let expected = bits[..i].iter().filter(|b| **b).count();
+                        assert_eq!(scanner.count_ones_before(i), expected);
+
+                        let expected = (i..len).find(|&j| bits[j]);
+                        assert_eq!(scanner.next_set_bit(i), expected);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bitmap_scanner_from_bitmap() {
+        let bitmap = Bitmap::from([true, true, false, false, false, true]);
+        let runs = BitmapScanner::from_bitmap(&bitmap)
+            .map(|run| (run.start, run.len, run.is_set, run.set_before))
+            .collect::<Vec<_>>();
+        assert_eq!(runs, &[(0, 2, true, 0), (2, 3, false, 2), (5, 1, true, 2)]);
+    }
+}
@@ -0,0 +1,246 @@
+use std::ops::AddAssign;
+
+use arrow::array::PrimitiveArray;
+use arrow::types::NativeType;
+use num::Float;
+
+use super::collect::CollectEwm;
+use super::state::ewm_mean_step;
+use super::validate_alpha;
+use crate::error::{PolarsError, Result};
+use crate::trusted_len::TrustedLen;
+
+/// Unadjusted exponentially weighted mean whose smoothing factor grows with the
+/// local volatility.
+///
+/// With the innovation `e = x - mean` against the previous mean, a running variance
+/// of the innovations `v = (1 - base_alpha) * v + base_alpha * e^2` is kept, and each
+/// observation is weighted with
+///
+/// ```text
+/// alpha = min(1, base_alpha * (1 + vol_sensitivity * max(e^2 / v - 1, 0)))
+/// ```
+///
+/// using `v` from before the update. In calm periods the squared innovations are
+/// close to their running variance and the smoother behaves like a plain EWM with
+/// `base_alpha`; a burst of volatility makes it track the input faster. While `v` is
+/// still zero no adaptation happens. Nulls carry the previous mean forward.
+pub fn ewm_mean_adaptive<I, T>(
+    xs: I,
+    base_alpha: T,
+    vol_sensitivity: T,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut opt_mean: Option<T> = None;
+    let mut innovation_var = T::zero();
+    let mut non_null_cnt = 0usize;
+
+    xs.into_iter()
+        .map(|opt_x| {
+            if let Some(x) = opt_x {
+                non_null_cnt += 1;
+                opt_mean = Some(match opt_mean {
+                    None => x,
+                    Some(prev_mean) => {
+                        let e_sqr = (x - prev_mean) * (x - prev_mean);
+                        let alpha = if innovation_var.is_zero() {
+                            base_alpha
+                        } else {
+                            let surprise = (e_sqr / innovation_var - T::one()).max(T::zero());
+                            (base_alpha * (T::one() + vol_sensitivity * surprise)).min(T::one())
+                        };
+                        innovation_var =
+                            (T::one() - base_alpha) * innovation_var + base_alpha * e_sqr;
+                        prev_mean + (x - prev_mean) * alpha
+                    }
+                });
+            }
+            match non_null_cnt < min_periods {
+                true => None,
+                false => opt_mean,
+            }
+        })
+        .collect_ewm()
+}
+
+/// Unadjusted exponentially weighted mean that treats nulls as growing uncertainty
+/// about the current mean, returned together with that uncertainty.
+///
+/// Every null adds `uncertainty_growth` to the uncertainty `u`, relative to the noise
+/// of a single observation. The next observation is weighted with
+/// `1 - (1 - alpha) / (1 + u)` instead of `alpha`, so after a long gap the mean moves
+/// further towards it, and `u` is reset to zero. Without nulls, or with
+/// `uncertainty_growth=0`, this is the unadjusted [`ewm_mean`](super::ewm_mean).
+/// The uncertainty is null before the first observation.
+pub fn ewm_mean_uncertainty<I, T>(
+    xs: I,
+    alpha: T,
+    uncertainty_growth: T,
+    min_periods: usize,
+) -> (PrimitiveArray<T>, PrimitiveArray<T>)
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    let mut uncertainty = Vec::with_capacity(xs.size_hint().0);
+
+    let mut opt_mean: Option<T> = None;
+    let mut u = T::zero();
+    let mut non_null_cnt = 0usize;
+
+    let means = xs
+        .map(|opt_x| {
+            match (opt_x, opt_mean) {
+                (Some(x), None) => opt_mean = Some(x),
+                (Some(x), Some(prev_mean)) => {
+                    let wgt = match u.is_zero() {
+                        true => alpha,
+                        false => T::one() - (T::one() - alpha) / (T::one() + u),
+                    };
+                    opt_mean = Some(prev_mean + (x - prev_mean) * wgt);
+                    u = T::zero();
+                }
+                (None, Some(_)) => u += uncertainty_growth,
+                (None, None) => {}
+            }
+            non_null_cnt += opt_x.is_some() as usize;
+            uncertainty.push(opt_mean.map(|_| u));
+            match non_null_cnt < min_periods {
+                true => None,
+                false => opt_mean,
+            }
+        })
+        .collect_ewm();
+    (means, PrimitiveArray::from(uncertainty))
+}
+
+/// Exponentially weighted mean with an explicit smoothing factor for every step.
+///
+/// The observation at position `i` is weighted with `alphas[i]`, and otherwise the
+/// recurrence is that of [`ewm_mean`](super::ewm_mean): with `adjust` the weights are
+/// normalized by their running sum. Time-based or regime-based decay can be expressed
+/// by computing `alphas` upfront. The `alphas` at null positions are not used.
+pub fn ewm_mean_varying_alpha<I, T>(
+    xs: I,
+    alphas: &[T],
+    adjust: bool,
+    min_periods: usize,
+) -> Result<PrimitiveArray<T>>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    if xs.size_hint().0 != alphas.len() {
+        return Err(PolarsError::ComputeError(
+            "alphas must have the same length as the input".into(),
+        ));
+    }
+    for &alpha in alphas {
+        validate_alpha(alpha)?;
+    }
+
+    let mut opt_mean: Option<T> = None;
+    let mut wgt_sum = if adjust { T::zero() } else { T::one() };
+    let mut non_null_cnt = 0usize;
+
+    Ok(xs
+        .zip(alphas.iter())
+        .map(|(opt_x, &alpha)| {
+            if let Some(x) = opt_x {
+                non_null_cnt += 1;
+                let (mean, next_wgt_sum) = ewm_mean_step(opt_mean, wgt_sum, x, alpha);
+                opt_mean = Some(mean);
+                wgt_sum = next_wgt_sum;
+            }
+            match non_null_cnt < min_periods {
+                true => None,
+                false => opt_mean,
+            }
+        })
+        .collect_ewm())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kernels::ewm::ewm_mean;
+
+    #[test]
+    fn test_ewm_mean_adaptive() {
+        let xs = (0..60)
+            .map(|i| {
+                let noise = if i % 2 == 0 { 0.1 } else { -0.1 };
+                let level = if i < 50 { 0.0 } else { 10.0 };
+                Some(level + noise)
+            })
+            .collect::<Vec<_>>();
+
+        let plain = ewm_mean(xs.clone(), 0.1, false, 1);
+        let adaptive = ewm_mean_adaptive(xs.clone(), 0.1, 1.0, 1);
+        for i in 50..53 {
+            assert!((adaptive.value(i) - 10.0).abs() < (plain.value(i) - 10.0).abs());
+        }
+
+        // without sensitivity it is a plain exponentially weighted mean
+        let adaptive = ewm_mean_adaptive(xs.clone(), 0.1, 0.0, 1);
+        assert!(adaptive
+            .values()
+            .iter()
+            .zip(plain.values().iter())
+            .all(|(a, b)| (a - b).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_ewm_mean_varying_alpha() {
+        let xs = vec![Some(2.0f64), None, Some(-1.0), Some(4.0), None, Some(0.5)];
+        for adjust in [false, true] {
+            let expected = ewm_mean(xs.clone(), 0.3, adjust, 2);
+            let out = ewm_mean_varying_alpha(xs.clone(), &[0.3; 6], adjust, 2).unwrap();
+            assert_eq!(out, expected);
+        }
+
+        // a step with `alpha=1` forgets everything before it
+        let alphas = [0.5, 0.5, 1.0, 0.5, 0.5, 0.5];
+        let out = ewm_mean_varying_alpha(xs.clone(), &alphas, true, 1).unwrap();
+        assert_eq!(out.value(2), -1.0);
+        assert_eq!(out.value(3), 1.5);
+
+        assert!(ewm_mean_varying_alpha(xs.clone(), &[0.5; 5], true, 1).is_err());
+        let alphas = [0.5, 0.5, f64::NAN, 0.5, 0.5, 0.5];
+        assert!(ewm_mean_varying_alpha(xs, &alphas, true, 1).is_err());
+
+        // shares the exactness of `ewm_mean` on a constant input
+        let out = ewm_mean_varying_alpha(vec![Some(-0.0f64); 3], &[0.2, 0.7, 0.4], true, 1);
+        assert!(out.unwrap().values().iter().all(|x| x.is_sign_negative()));
+    }
+
+    #[test]
+    fn test_ewm_mean_uncertainty() {
+        let mut xs = vec![Some(1.0f64); 5];
+        xs.extend([None; 10]);
+        xs.push(Some(11.0));
+
+        let (means, uncertainty) = ewm_mean_uncertainty(xs.clone(), 0.1, 0.5, 1);
+        assert_eq!(uncertainty.value(14), 5.0);
+        assert_eq!(uncertainty.value(15), 0.0);
+        // weight `1 - 0.9 / 6 = 0.85` instead of `0.1`
+        assert!((means.value(15) - 9.5).abs() < 1e-12);
+        let plain = ewm_mean(xs.clone(), 0.1, false, 1);
+        assert!(means.value(15) > plain.value(15) + 5.0);
+
+        let (means, _) = ewm_mean_uncertainty(xs.clone(), 0.1, 0.0, 1);
+        assert_eq!(means, plain);
+
+        let (_, uncertainty) = ewm_mean_uncertainty(vec![None, Some(1.0)], 0.1, 0.5, 1);
+        assert_eq!(uncertainty, PrimitiveArray::from([None, Some(0.0)]));
+    }
+}
@@ -0,0 +1,394 @@
+use std::ops::AddAssign;
+
+use arrow::array::{Array, DictionaryArray, DictionaryKey, FixedSizeListArray, PrimitiveArray};
+use arrow::bitmap::MutableBitmap;
+use arrow::datatypes::{DataType, IntegerType};
+use arrow::types::NativeType;
+use num::Float;
+
+use super::{ewm_mean, EwmMeanState};
+use crate::bitmap::scanner::BitmapScanner;
+use crate::error::{PolarsError, Result};
+use crate::trusted_len::TrustedLen;
+
+fn downcast_values<T: NativeType>(arr: &dyn Array) -> Result<&PrimitiveArray<T>> {
+    arr.as_any()
+        .downcast_ref::<PrimitiveArray<T>>()
+        .ok_or_else(|| {
+            PolarsError::ComputeError(
+                format!("ewm_mean is not supported for {:?}", arr.data_type()).into(),
+            )
+        })
+}
+
+fn ewm_mean_dictionary<K, T>(
+    arr: &dyn Array,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> Result<PrimitiveArray<T>>
+where
+    K: DictionaryKey,
+    T: Float + NativeType + AddAssign,
+{
+    let arr = arr.as_any().downcast_ref::<DictionaryArray<K>>().unwrap();
+    let values = downcast_values::<T>(arr.values().as_ref())?;
+
+    let xs = arr.keys().iter().map(|opt_key| {
+        let idx: usize = (*opt_key?).try_into().ok()?;
+        values.is_valid(idx).then(|| values.value(idx))
+    });
+    Ok(ewm_mean(xs, alpha, adjust, min_periods))
+}
+
+/// [`ewm_mean`] over an arrow array of floats.
+///
+/// Dictionary-encoded arrays are decoded in logical order on the fly.
+pub fn ewm_mean_array<T>(
+    arr: &dyn Array,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> Result<PrimitiveArray<T>>
+where
+    T: Float + NativeType + AddAssign,
+{
+    match arr.data_type().to_logical_type() {
+        DataType::Dictionary(key_type, _, _) => {
+            use IntegerType::*;
+            match key_type {
+                Int8 => ewm_mean_dictionary::<i8, T>(arr, alpha, adjust, min_periods),
+                Int16 => ewm_mean_dictionary::<i16, T>(arr, alpha, adjust, min_periods),
+                Int32 => ewm_mean_dictionary::<i32, T>(arr, alpha, adjust, min_periods),
+                Int64 => ewm_mean_dictionary::<i64, T>(arr, alpha, adjust, min_periods),
+                UInt8 => ewm_mean_dictionary::<u8, T>(arr, alpha, adjust, min_periods),
+                UInt16 => ewm_mean_dictionary::<u16, T>(arr, alpha, adjust, min_periods),
+                UInt32 => ewm_mean_dictionary::<u32, T>(arr, alpha, adjust, min_periods),
+                UInt64 => ewm_mean_dictionary::<u64, T>(arr, alpha, adjust, min_periods),
+            }
+        }
+        _ => {
+            let arr = downcast_values::<T>(arr)?;
+            Ok(ewm_mean(
+                arr.iter().map(|opt_x| opt_x.copied()),
+                alpha,
+                adjust,
+                min_periods,
+            ))
+        }
+    }
+}
+
+/// Chain the values of `chunks` into a single iterator that is [`TrustedLen`] without
+/// an `unsafe` length claim. The chain is built as a balanced tree, so that every value
+/// passes through `O(log n)` levels of it.
+fn chain_chunks<'a, T: NativeType>(
+    chunks: &[&'a PrimitiveArray<T>],
+) -> Box<dyn TrustedLen<Item = Option<T>> + 'a> {
+    match chunks {
+        [] => Box::new(Vec::new().into_iter()),
+        [arr] => Box::new(arr.iter().map(|opt_x| opt_x.copied())),
+        _ => {
+            let (left, right) = chunks.split_at(chunks.len() / 2);
+            Box::new(chain_chunks(left).chain(chain_chunks(right)))
+        }
+    }
+}
+
+/// [`ewm_mean`] over the concatenation of `chunks`, without concatenating them.
+pub fn ewm_mean_chunks<T>(
+    chunks: &[&PrimitiveArray<T>],
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    T: Float + NativeType + AddAssign,
+{
+    ewm_mean(chain_chunks(chunks), alpha, adjust, min_periods)
+}
+
+/// [`ewm_mean`] down each column of a `FixedSizeListArray`, read as a matrix with
+/// one row per list.
+///
+/// A slot is skipped if it is null or in a null row. Rows that are null in the
+/// input are null in the output too.
+pub fn ewm_mean_fixed_size_list<T>(
+    arr: &FixedSizeListArray,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> Result<FixedSizeListArray>
+where
+    T: Float + NativeType + AddAssign,
+{
+    let width = match arr.data_type().to_logical_type() {
+        DataType::FixedSizeList(_, width) => *width,
+        _ => unreachable!(),
+    };
+    let values = downcast_values::<T>(arr.values().as_ref())?;
+    let n_rows = arr.len();
+
+    let mut out = vec![None; n_rows * width];
+    for col in 0..width {
+        let xs = (0..n_rows).map(|row| {
+            let idx = row * width + col;
+            (arr.is_valid(row) && values.is_valid(idx)).then(|| values.value(idx))
+        });
+        let smoothed = ewm_mean(xs, alpha, adjust, min_periods);
+        for (row, opt_v) in smoothed.iter().enumerate() {
+            out[row * width + col] = opt_v.copied();
+        }
+    }
+    Ok(FixedSizeListArray::new(
+        arr.data_type().clone(),
+        Box::new(PrimitiveArray::from(out).to(values.data_type().clone())),
+        arr.validity().cloned(),
+    ))
+}
+
+/// [`ewm_mean`] of an array that walks the runs of its validity bitmap with a
+/// [`BitmapScanner`], instead of visiting the nulls one by one.
+///
+/// Between two observations the output is constant, so runs of nulls are filled as a
+/// block. This pays off on sparse data. The output has the data type of `arr`.
+pub fn ewm_mean_sparse<T>(
+    arr: &PrimitiveArray<T>,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    T: Float + NativeType + AddAssign,
+{
+    let validity = match arr.validity() {
+        Some(validity) if validity.unset_bits() > 0 => validity,
+        _ => {
+            return ewm_mean(
+                arr.values().iter().map(|x| Some(*x)),
+                alpha,
+                adjust,
+                min_periods,
+            )
+            .to(arr.data_type().clone())
+        }
+    };
+    let values = arr.values().as_slice();
+    let len = values.len();
+
+    let mut out = Vec::with_capacity(len);
+    let mut out_validity = MutableBitmap::with_capacity(len);
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+
+    for run in BitmapScanner::from_bitmap(validity) {
+        let end = run.start + run.len;
+        if run.is_set {
+            for &x in &values[run.start..end] {
+                let opt_mean = state.update(Some(x));
+                out.push(opt_mean.unwrap_or_default());
+                out_validity.push(opt_mean.is_some());
+            }
+        } else {
+            // the output at a null does not change the state
+            let opt_mean = state.update(None);
+            out.resize(end, opt_mean.unwrap_or_default());
+            out_validity.extend_constant(run.len, opt_mean.is_some());
+        }
+    }
+
+    PrimitiveArray::new(
+        arr.data_type().clone(),
+        out.into(),
+        Some(out_validity.into()),
+    )
+}
+
+/// [`ewm_mean`] of a sparse series of length `len`, given as the `indices` of its
+/// observations and their `values`.
+///
+/// Absent positions are nulls: they do not change the state and carry the previous
+/// output forward. The recurrence only runs over the observations, and the dense
+/// output is materialized in between.
+pub fn ewm_mean_sparse_indices<T>(
+    indices: &[usize],
+    values: &[T],
+    len: usize,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> Result<PrimitiveArray<T>>
+where
+    T: Float + NativeType + AddAssign,
+{
+    if indices.len() != values.len() {
+        return Err(PolarsError::ComputeError(
+            "indices and values must have the same length".into(),
+        ));
+    }
+    if indices.windows(2).any(|w| w[0] >= w[1]) || matches!(indices.last(), Some(&i) if i >= len) {
+        return Err(PolarsError::ComputeError(
+            "indices must be strictly increasing and smaller than len".into(),
+        ));
+    }
+
+    let mut out = Vec::with_capacity(len);
+    let mut out_validity = MutableBitmap::with_capacity(len);
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+
+    for (&idx, &x) in indices.iter().zip(values) {
+        // the output at a null does not change the state
+        let opt_mean = state.update(None);
+        out_validity.extend_constant(idx - out.len(), opt_mean.is_some());
+        out.resize(idx, opt_mean.unwrap_or_default());
+
+        let opt_mean = state.update(Some(x));
+        out.push(opt_mean.unwrap_or_default());
+        out_validity.push(opt_mean.is_some());
+    }
+    let opt_mean = state.update(None);
+    out_validity.extend_constant(len - out.len(), opt_mean.is_some());
+    out.resize(len, opt_mean.unwrap_or_default());
+
+    Ok(PrimitiveArray::new(
+        T::PRIMITIVE.into(),
+        out.into(),
+        Some(out_validity.into()),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ewm_mean_dictionary() {
+        let values = PrimitiveArray::from([Some(1.0f64), Some(3.0), None]);
+        let keys = PrimitiveArray::from([Some(0u32), Some(1), Some(1), None, Some(2), Some(0)]);
+        let arr = DictionaryArray::try_from_keys(keys, Box::new(values)).unwrap();
+
+        let result = ewm_mean_array(&arr, 0.5, true, 1).unwrap();
+        let decoded = vec![Some(1.0), Some(3.0), Some(3.0), None, None, Some(1.0)];
+        assert_eq!(result, ewm_mean(decoded, 0.5, true, 1));
+
+        let plain = PrimitiveArray::from([Some(1.0f64), None, Some(3.0)]);
+        let result = ewm_mean_array(&plain, 0.5, true, 1).unwrap();
+        assert_eq!(
+            result,
+            ewm_mean(plain.iter().map(|x| x.copied()), 0.5, true, 1)
+        );
+
+        assert!(ewm_mean_array::<f32>(&arr, 0.5, true, 1).is_err());
+    }
+
+    #[test]
+    fn test_ewm_mean_fixed_size_list() {
+        let values = PrimitiveArray::from([
+            Some(1.0f64),
+            None,
+            Some(3.0),
+            Some(3.0),
+            Some(4.0),
+            Some(-1.0),
+        ]);
+        let data_type = DataType::FixedSizeList(
+            Box::new(arrow::datatypes::Field::new(
+                "item",
+                DataType::Float64,
+                true,
+            )),
+            3,
+        );
+        let arr = FixedSizeListArray::new(data_type, Box::new(values), None);
+
+        let result = ewm_mean_fixed_size_list(&arr, 0.5, true, 1).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result.data_type(), arr.data_type());
+        let result = result
+            .values()
+            .as_any()
+            .downcast_ref::<PrimitiveArray<f64>>()
+            .unwrap();
+        // columns [1, 3], [null, 4] and [3, -1]
+        let expected = PrimitiveArray::from([
+            Some(1.0),
+            None,
+            Some(3.0),
+            Some(1.0 + 2.0 * 0.5 / 0.75),
+            Some(4.0),
+            Some(3.0 - 4.0 * 0.5 / 0.75),
+        ]);
+        assert_eq!(result, &expected);
+    }
+
+    #[test]
+    fn test_ewm_mean_sparse() {
+        // crosses several words of the bitmap, with valid slots at word boundaries
+        let xs = (0..300)
+            .map(|i| (i % 11 == 0 || i == 63 || i == 64).then_some((i % 7) as f64 - 3.0))
+            .collect::<Vec<_>>();
+        let arr = PrimitiveArray::from(xs.clone());
+
+        for (alpha, adjust, min_periods) in [(0.3, true, 1), (0.3, false, 4), (1.0, true, 1)] {
+            let expected = ewm_mean(xs.clone(), alpha, adjust, min_periods);
+            assert_eq!(ewm_mean_sparse(&arr, alpha, adjust, min_periods), expected);
+            // a sliced array starts in the middle of a byte
+            let sliced = arr.slice(5, 250);
+            let expected = ewm_mean(xs[5..255].to_vec(), alpha, adjust, min_periods);
+            assert_eq!(
+                ewm_mean_sparse(&sliced, alpha, adjust, min_periods),
+                expected
+            );
+        }
+        let dense = PrimitiveArray::from_vec(vec![1.0f64, 2.0, 3.0]);
+        assert_eq!(
+            ewm_mean_sparse(&dense, 0.5, true, 1),
+            ewm_mean(vec![Some(1.0), Some(2.0), Some(3.0)], 0.5, true, 1)
+        );
+
+        // the data type is kept with and without nulls
+        let data_type = DataType::Extension("ext".to_string(), Box::new(DataType::Float64), None);
+        for arr in [dense, arr] {
+            let arr = arr.to(data_type.clone());
+            assert_eq!(ewm_mean_sparse(&arr, 0.5, true, 1).data_type(), &data_type);
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_sparse_indices() {
+        let (indices, values) = ([2, 3, 7, 8], [1.0f64, 4.0, -2.0, 0.5]);
+        let mut dense = vec![None; 11];
+        for (&i, &v) in indices.iter().zip(&values) {
+            dense[i] = Some(v);
+        }
+        for (alpha, adjust) in [(0.3, false), (0.3, true), (1.0, true)] {
+            let out = ewm_mean_sparse_indices(&indices, &values, 11, alpha, adjust, 2).unwrap();
+            assert_eq!(out, ewm_mean(dense.clone(), alpha, adjust, 2));
+        }
+
+        let out = ewm_mean_sparse_indices(&[], &[], 3, 0.5f64, true, 1).unwrap();
+        assert_eq!(out, PrimitiveArray::from([None, None, None]));
+        assert!(ewm_mean_sparse_indices(&[3, 2], &[1.0f64, 2.0], 5, 0.5, true, 1).is_err());
+        assert!(ewm_mean_sparse_indices(&[5], &[1.0f64], 5, 0.5, true, 1).is_err());
+        assert!(ewm_mean_sparse_indices(&[1], &[1.0f64, 2.0], 5, 0.5, true, 1).is_err());
+    }
+
+    #[test]
+    fn test_ewm_mean_chunks() {
+        let xs = (0..100)
+            .map(|i| (i % 7 != 3).then_some((i % 11) as f64))
+            .collect::<Vec<_>>();
+        let expected = ewm_mean(xs.clone(), 0.3, true, 2);
+
+        for n_chunks in [1, 2, 5, 13] {
+            let arrays = xs
+                .chunks((xs.len() + n_chunks - 1) / n_chunks)
+                .map(|chunk| PrimitiveArray::from(chunk.to_vec()))
+                .collect::<Vec<_>>();
+            let chunks = arrays.iter().collect::<Vec<_>>();
+            assert_eq!(chain_chunks(&chunks).size_hint(), (100, Some(100)));
+            assert_eq!(ewm_mean_chunks(&chunks, 0.3, true, 2), expected);
+        }
+        assert_eq!(chain_chunks::<f64>(&[]).size_hint(), (0, Some(0)));
+        assert_eq!(ewm_mean_chunks::<f64>(&[], 0.3, true, 2).len(), 0);
+    }
+}
@@ -1,188 +1,2092 @@
 use std::ops::AddAssign;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use arrow::array::PrimitiveArray;
+use arrow::array::{BooleanArray, PrimitiveArray};
+use arrow::bitmap::{Bitmap, MutableBitmap};
 use arrow::types::NativeType;
 use num::Float;
 
+use super::collect::CollectEwm;
+use super::{validate_alpha, EwmMeanState};
+use crate::bitmap::scanner::BitmapScanner;
+use crate::error::Result;
 use crate::trusted_len::TrustedLen;
-use crate::utils::CustomIterTools;
+use crate::utils::combine_validities;
 
+/// Exponentially weighted mean of `xs`.
+///
+/// The output is reproducible to the bit across platforms: the recurrence only uses
+/// correctly rounded additions, multiplications and divisions in a fixed order, and
+/// Rust never fuses them into FMA instructions.
 pub fn ewm_mean<I, T>(xs: I, alpha: T, adjust: bool, min_periods: usize) -> PrimitiveArray<T>
 where
     I: IntoIterator<Item = Option<T>>,
     I::IntoIter: TrustedLen,
     T: Float + NativeType + AddAssign,
 {
-    if alpha.is_one() {
-        return ewm_mean_alpha_equals_one(xs, min_periods);
+    ewm_mean_iter(xs, alpha, adjust, min_periods).collect_ewm()
+}
+
+/// [`ewm_mean`] that validates `alpha` first.
+///
+/// An `alpha` derived from a bad span or half-life can be `NaN` or infinite, which
+/// [`ewm_mean`] turns into an all-`NaN` output. This returns an error instead, as well
+/// as for a finite `alpha` outside of `(0, 1]`.
+pub fn checked_ewm_mean<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> Result<PrimitiveArray<T>>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    validate_alpha(alpha)?;
+    Ok(ewm_mean(xs, alpha, adjust, min_periods))
+}
+
+/// [`ewm_mean`] over fallible input, e.g. values that are parsed lazily.
+///
+/// Returns the first error; the output computed so far is dropped.
+pub fn try_ewm_mean<I, T, E>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> std::result::Result<PrimitiveArray<T>, E>
+where
+    I: IntoIterator<Item = std::result::Result<Option<T>, E>>,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    let mut out = Vec::with_capacity(xs.size_hint().0);
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    for opt_x in xs {
+        out.push(state.update(opt_x?));
+    }
+    Ok(PrimitiveArray::from(out))
+}
+
+/// Lazily computes the output of [`ewm_mean`], so that variants can consume the means
+/// without materializing them.
+pub(super) fn ewm_mean_iter<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> impl TrustedLen<Item = Option<T>>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    xs.into_iter().map(move |opt_x| state.update(opt_x))
+}
+
+/// [`ewm_mean`] over values and validity stored as two parallel slices.
+///
+/// # Panics
+/// Panics if `values` and `valid` differ in length.
+pub fn ewm_mean_soa<T>(
+    values: &[T],
+    valid: &[bool],
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    T: Float + NativeType + AddAssign,
+{
+    assert_eq!(
+        values.len(),
+        valid.len(),
+        "values and validity must have equal length"
+    );
+    let xs = values
+        .iter()
+        .zip(valid)
+        .map(|(&x, &is_valid)| if is_valid { Some(x) } else { None });
+    ewm_mean(xs, alpha, adjust, min_periods)
+}
+
+/// [`ewm_mean`] that discards the first `burn_in` non-null observations.
+///
+/// Unlike `min_periods`, the discarded observations do not contribute to the state
+/// either; the average effectively starts at observation `burn_in + 1`. The discarded
+/// positions are null in the output.
+pub fn ewm_mean_burn_in<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    burn_in: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut skipped = 0usize;
+    let xs = xs.into_iter().map(move |opt_x| match opt_x {
+        Some(_) if skipped < burn_in => {
+            skipped += 1;
+            None
+        }
+        _ => opt_x,
+    });
+    ewm_mean(xs, alpha, adjust, min_periods)
+}
+
+/// [`ewm_mean`] that only emits every `stride`-th output, starting at the first one.
+///
+/// The recurrence still runs over every input; only the output is downsampled.
+///
+/// # Panics
+/// Panics if `stride == 0`.
+pub fn ewm_mean_downsampled<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    stride: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    assert!(stride > 0);
+    let means = ewm_mean_iter(xs, alpha, adjust, min_periods);
+    let mut out = Vec::with_capacity(means.size_hint().0 / stride + 1);
+    for (i, opt_mean) in means.enumerate() {
+        if i % stride == 0 {
+            out.push(opt_mean);
+        }
+    }
+    PrimitiveArray::from(out)
+}
+
+/// [`ewm_mean`] averaged over consecutive blocks of `block` outputs.
+///
+/// Nulls are left out of a block's average and a block of only nulls is null. A
+/// trailing partial block is averaged over the outputs it has.
+///
+/// # Panics
+/// Panics if `block == 0`.
+pub fn ewm_mean_block_average<I, T>(
+    xs: I,
+    alpha: T,
+    block: usize,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    assert!(block > 0);
+    let means = ewm_mean_iter(xs, alpha, adjust, min_periods);
+    let len = means.size_hint().0;
+    let mut out = Vec::with_capacity(len / block + 1);
+
+    let mut sum = T::zero();
+    let mut cnt = 0usize;
+    for (i, opt_mean) in means.enumerate() {
+        if let Some(mean) = opt_mean {
+            sum += mean;
+            cnt += 1;
+        }
+        if (i + 1) % block == 0 || i + 1 == len {
+            out.push((cnt > 0).then(|| sum / T::from(cnt).unwrap()));
+            sum = T::zero();
+            cnt = 0;
+        }
+    }
+    PrimitiveArray::from(out)
+}
+
+/// [`ewm_mean`] over only the rows where `mask` is `true`.
+///
+/// The selected rows are averaged in order as if they were contiguous and the results
+/// are written back at their original positions. Rows where `mask` is `false` or null
+/// are null in the output and do not affect the state.
+///
+/// # Panics
+/// Panics if `xs` and `mask` differ in length.
+pub fn ewm_mean_masked<I, T>(
+    xs: I,
+    mask: &BooleanArray,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut xs = xs.into_iter();
+    let len = mask.len();
+    assert_eq!(
+        xs.size_hint().0,
+        len,
+        "mask must have the same length as the input"
+    );
+    let selected = combine_validities(Some(mask.values()), mask.validity()).unwrap();
+
+    let mut out = Vec::with_capacity(len);
+    let mut out_validity = MutableBitmap::with_capacity(len);
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+
+    for run in BitmapScanner::from_bitmap(&selected) {
+        if run.is_set {
+            for opt_x in xs.by_ref().take(run.len) {
+                let opt_mean = state.update(opt_x);
+                out.push(opt_mean.unwrap_or_default());
+                out_validity.push(opt_mean.is_some());
+            }
+        } else {
+            xs.nth(run.len - 1);
+            out.resize(run.start + run.len, T::default());
+            out_validity.extend_constant(run.len, false);
+        }
+    }
+    PrimitiveArray::new(T::PRIMITIVE.into(), out.into(), Some(out_validity.into()))
+}
+
+/// [`ewm_mean`] that starts from `seed`, as if it had been observed once before the
+/// input, see [`EwmMeanState::with_seed`].
+///
+/// A seed far from the data pulls the first outputs outside the range of the
+/// observations. With `clamp_to_input_range` every output is clamped to the running
+/// minimum and maximum of the observations so far.
+pub fn ewm_mean_seeded<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    seed: T,
+    clamp_to_input_range: bool,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut state = EwmMeanState::with_seed(alpha, adjust, min_periods, seed);
+    let mut opt_range: Option<(T, T)> = None;
+
+    xs.into_iter()
+        .map(|opt_x| {
+            let opt_mean = state.update(opt_x);
+            if !clamp_to_input_range {
+                return opt_mean;
+            }
+            if let Some(x) = opt_x {
+                opt_range = Some(match opt_range {
+                    None => (x, x),
+                    Some((min, max)) => (min.min(x), max.max(x)),
+                });
+            }
+            match (opt_mean, opt_range) {
+                (Some(mean), Some((min, max))) => Some(mean.max(min).min(max)),
+                _ => opt_mean,
+            }
+        })
+        .collect_ewm()
+}
+
+/// [`ewm_mean`] of the ratio `num / den`, computed in a single pass.
+///
+/// The ratio is null where either input is null or `den` is zero, and such positions
+/// are treated as nulls by the average.
+///
+/// # Panics
+/// Panics if `num` and `den` differ in length.
+pub fn ewm_mean_ratio<I, J, T>(
+    num: I,
+    den: J,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    J: IntoIterator<Item = Option<T>>,
+    J::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let (num, den) = (num.into_iter(), den.into_iter());
+    assert_eq!(
+        num.size_hint().0,
+        den.size_hint().0,
+        "num and den must have the same length"
+    );
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    num.zip(den)
+        .map(|(opt_num, opt_den)| {
+            let ratio = match (opt_num, opt_den) {
+                (Some(num), Some(den)) if !den.is_zero() => Some(num / den),
+                _ => None,
+            };
+            state.update(ratio)
+        })
+        .collect_ewm()
+}
+
+/// [`ewm_mean`] evaluated only at the positions of the observations.
+///
+/// Returns the indices of the non-null inputs and the output at each of them. At the
+/// null positions in between, [`ewm_mean`] with `alpha < 1` carries the previous
+/// output forward, so nothing is lost when the nulls are dense.
+pub fn ewm_mean_compact<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> (Vec<usize>, PrimitiveArray<T>)
+where
+    I: IntoIterator<Item = Option<T>>,
+    T: Float + NativeType + AddAssign,
+{
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    let (idx, out): (Vec<_>, Vec<_>) = xs
+        .into_iter()
+        .enumerate()
+        .filter(|(_, opt_x)| opt_x.is_some())
+        .map(|(i, opt_x)| (i, state.update(opt_x)))
+        .unzip();
+    (idx, PrimitiveArray::from(out))
+}
+
+/// [`ewm_mean`] that holds the mean at the positions set in `freeze`.
+///
+/// At a frozen position the input is ignored and the current mean is emitted
+/// unchanged. Unlike a null, a frozen position does not affect the state at all.
+///
+/// # Panics
+/// Panics if `xs` and `freeze` differ in length.
+pub fn ewm_mean_frozen<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    freeze: Option<&Bitmap>,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    if let Some(freeze) = freeze {
+        assert_eq!(
+            xs.size_hint().0,
+            freeze.len(),
+            "freeze must have the same length as the input"
+        );
+    }
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    xs.enumerate()
+        .map(|(i, opt_x)| match freeze {
+            Some(freeze) if freeze.get_bit(i) => state.mean(),
+            _ => state.update(opt_x),
+        })
+        .collect_ewm()
+}
+
+/// [`ewm_mean`] that drops the positions set in `paused` entirely.
+///
+/// Unlike a null or a frozen position (see [`ewm_mean_frozen`]), a paused position
+/// neither updates the state nor emits an output, so the output has one value less
+/// for every set bit in `paused`.
+///
+/// # Panics
+/// Panics if `xs` and `paused` differ in length.
+pub fn ewm_mean_paused<I, T>(
+    xs: I,
+    paused: &Bitmap,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    assert_eq!(
+        xs.size_hint().0,
+        paused.len(),
+        "paused must have the same length as the input"
+    );
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    xs.zip(paused.iter())
+        .filter(|(_, is_paused)| !is_paused)
+        .map(|(opt_x, _)| state.update(opt_x))
+        .collect()
+}
+
+/// [`ewm_mean`] that clears its state once `reset_after_nulls` consecutive nulls occur.
+///
+/// After a reset the output is null until the next observation, which seeds a fresh
+/// average (including the `min_periods` count). `None` never resets, and `Some(0)`
+/// resets at every null like `Some(1)`, as a run of nulls has at least one null. The
+/// runs of nulls are found with a [`BitmapScanner`] over the validity of `arr`. The
+/// output has the data type of `arr`.
+pub fn ewm_mean_with_null_reset<T>(
+    arr: &PrimitiveArray<T>,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    reset_after_nulls: Option<usize>,
+) -> PrimitiveArray<T>
+where
+    T: Float + NativeType + AddAssign,
+{
+    let values = arr.values().as_slice();
+    let validity = match arr.validity() {
+        Some(validity) if validity.unset_bits() > 0 => validity,
+        _ => {
+            return ewm_mean(values.iter().map(|x| Some(*x)), alpha, adjust, min_periods)
+                .to(arr.data_type().clone())
+        }
+    };
+    // the state is cleared at the `max_nulls`-th null of a run
+    let max_nulls = reset_after_nulls.unwrap_or(usize::MAX);
+    let len = values.len();
+
+    let mut out = Vec::with_capacity(len);
+    let mut out_validity = MutableBitmap::with_capacity(len);
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+
+    // the output at a null does not change the state
+    let fill_nulls =
+        |state: &mut EwmMeanState<T>, out: &mut Vec<T>, out_validity: &mut MutableBitmap, n| {
+            let opt_mean = state.update(None);
+            out.resize(out.len() + n, opt_mean.unwrap_or_default());
+            out_validity.extend_constant(n, opt_mean.is_some());
+        };
+
+    for run in BitmapScanner::from_bitmap(validity) {
+        if run.is_set {
+            for &x in &values[run.start..run.start + run.len] {
+                let opt_mean = state.update(Some(x));
+                out.push(opt_mean.unwrap_or_default());
+                out_validity.push(opt_mean.is_some());
+            }
+        } else {
+            let kept = std::cmp::min(run.len, max_nulls.saturating_sub(1));
+            fill_nulls(&mut state, &mut out, &mut out_validity, kept);
+            if kept < run.len {
+                state.reset();
+                fill_nulls(&mut state, &mut out, &mut out_validity, run.len - kept);
+            }
+        }
+    }
+    PrimitiveArray::new(
+        arr.data_type().clone(),
+        out.into(),
+        Some(out_validity.into()),
+    )
+}
+
+/// [`ewm_mean`] across the columns of a single row, in column order.
+///
+/// Smoothing a matrix cross-sectionally means calling this once per row; the
+/// recurrence is the same as along a column.
+pub fn ewm_mean_cross_section<T>(
+    row: &[Option<T>],
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    T: Float + NativeType + AddAssign,
+{
+    ewm_mean(row.iter().copied(), alpha, adjust, min_periods)
+}
+
+/// [`ewm_mean`] that restarts whenever the sign of the input flips.
+///
+/// With `reset_on_sign_change` the first observation of a new sign regime seeds a
+/// fresh average (including the `min_periods` count). Nulls and zeros never start a
+/// new regime.
+pub fn ewm_mean_with_sign_reset<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    reset_on_sign_change: bool,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    let mut opt_negative: Option<bool> = None;
+
+    xs.into_iter()
+        .map(|opt_x| {
+            if let Some(x) = opt_x {
+                if reset_on_sign_change && !x.is_zero() {
+                    let negative = x < T::zero();
+                    if opt_negative == Some(!negative) {
+                        state = EwmMeanState::new(alpha, adjust, min_periods);
+                    }
+                    opt_negative = Some(negative);
+                }
+            }
+            state.update(opt_x)
+        })
+        .collect_ewm()
+}
+
+/// [`ewm_mean`] running from the end of `xs` towards the start.
+///
+/// The output is aligned with `xs`, i.e. position `i` holds the average over
+/// `xs[i..]`. The input is consumed through a reversed slice iterator, so no
+/// reversed copy of it is made.
+pub fn ewm_mean_reverse<T>(
+    xs: &[Option<T>],
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    T: Float + NativeType + AddAssign,
+{
+    ewm_mean_iter(xs.iter().rev().copied(), alpha, adjust, min_periods).collect_ewm_reversed()
+}
+
+/// [`ewm_mean`] that holds its value while observations stay within `deadband` of it.
+///
+/// An observation only updates the average if it differs from the current mean by
+/// more than `deadband`; otherwise it is ignored. Returns the means together with a
+/// mask that is `true` where the mean was updated, `false` where it was held and null
+/// where the input is null. `None` disables the deadband.
+///
+/// The deadband only applies once `min_periods` is reached: the warm-up observations
+/// always update the mean, so that observations close to the first one cannot hold
+/// the output at null indefinitely.
+pub fn ewm_mean_with_deadband<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    deadband: Option<T>,
+) -> (PrimitiveArray<T>, BooleanArray)
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    let mut updated = Vec::with_capacity(xs.size_hint().0);
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+
+    let means = xs
+        .map(|opt_x| {
+            let hold = match (opt_x, state.mean(), deadband) {
+                (Some(x), Some(mean), Some(band)) => (x - mean).abs() <= band,
+                _ => false,
+            };
+            updated.push(opt_x.map(|_| !hold));
+            match hold {
+                true => state.mean(),
+                false => state.update(opt_x),
+            }
+        })
+        .collect_ewm();
+    (means, BooleanArray::from(updated))
+}
+
+/// [`ewm_mean`] with its warm-up seeded by reflecting the start of the series.
+///
+/// Before the first position the first `reflect_len` non-null observations are fed
+/// to the average in reverse order, as if the series had been mirrored around its
+/// start. This gives the smoother phantom history and reduces the bias of the early
+/// outputs towards the very first observation. The phantom observations count
+/// towards `min_periods`.
+pub fn ewm_mean_reflect_start<I, T>(
+    xs: I,
+    alpha: T,
+    reflect_len: usize,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen + Clone,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    let head = xs.clone().flatten().take(reflect_len).collect::<Vec<_>>();
+
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    head.into_iter().rev().for_each(|x| {
+        state.update(Some(x));
+    });
+    xs.map(|opt_x| state.update(opt_x)).collect_ewm()
+}
+
+/// [`ewm_mean`] that reports its progress by adding to `progress` every `every`
+/// values, e.g. for a progress bar polling it from another thread.
+///
+/// On completion `progress` has been increased by the length of the input. Without
+/// a counter this is [`ewm_mean`].
+///
+/// # Panics
+/// Panics if `every == 0`.
+pub fn ewm_mean_with_progress<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    progress: Option<&AtomicUsize>,
+    every: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    assert!(every > 0, "every must be positive");
+    let progress = match progress {
+        Some(progress) => progress,
+        None => return ewm_mean(xs, alpha, adjust, min_periods),
+    };
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    let mut done = 0usize;
+    let out: PrimitiveArray<T> = xs
+        .into_iter()
+        .map(|opt_x| {
+            done += 1;
+            if done == every {
+                progress.fetch_add(every, Ordering::Relaxed);
+                done = 0;
+            }
+            state.update(opt_x)
+        })
+        .collect_ewm();
+    progress.fetch_add(done, Ordering::Relaxed);
+    out
+}
+
+/// [`ewm_mean`] that also passes every non-null output to `sink`, in order.
+///
+/// This allows accumulating statistics of the smoothed values, e.g. a histogram,
+/// without a second pass over the result.
+pub fn ewm_mean_with_sink<I, T, F>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    mut sink: F,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+    F: FnMut(T),
+{
+    ewm_mean_iter(xs, alpha, adjust, min_periods)
+        .map(|opt_mean| {
+            if let Some(mean) = opt_mean {
+                sink(mean)
+            }
+            opt_mean
+        })
+        .collect_ewm()
+}
+
+/// [`ewm_mean`] that starts over wherever the input equals `reset_value`.
+///
+/// The sentinel marks a reset and is not an observation itself: its position is null
+/// in the output and the next observation seeds a fresh average (including the
+/// `min_periods` count). A `NaN` sentinel matches `NaN` inputs.
+pub fn ewm_mean_reset_on<I, T>(
+    xs: I,
+    reset_value: T,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let is_sentinel = |x: T| x == reset_value || (x.is_nan() && reset_value.is_nan());
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+
+    xs.into_iter()
+        .map(|opt_x| match opt_x {
+            Some(x) if is_sentinel(x) => {
+                state = EwmMeanState::new(alpha, adjust, min_periods);
+                None
+            }
+            _ => state.update(opt_x),
+        })
+        .collect_ewm()
+}
+
+/// The raw accumulators of [`ewm_mean`]: the running weighted sum of the observations
+/// and the running sum of their weights.
+///
+/// Every observation gets weight `alpha` and older weights decay by `1 - alpha`; without
+/// `adjust` the first observation carries the full weight `1`. Dividing the sum by the
+/// weight gives the mean. Both are null before the first observation and nulls carry
+/// them forward.
+pub fn ewm_accumulators<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+) -> (PrimitiveArray<T>, PrimitiveArray<T>)
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    let mut weights = Vec::with_capacity(xs.size_hint().0);
+
+    let one_sub_alpha = T::one() - alpha;
+    let mut opt_acc: Option<(T, T)> = None;
+
+    let sums = xs
+        .map(|opt_x| {
+            if let Some(x) = opt_x {
+                opt_acc = Some(match opt_acc {
+                    Some((sum, wgt_sum)) => (
+                        one_sub_alpha * sum + alpha * x,
+                        one_sub_alpha * wgt_sum + alpha,
+                    ),
+                    None if adjust => (alpha * x, alpha),
+                    None => (x, T::one()),
+                });
+            }
+            weights.push(opt_acc.map(|(_, wgt_sum)| wgt_sum));
+            opt_acc.map(|(sum, _)| sum)
+        })
+        .collect_ewm();
+    (sums, PrimitiveArray::from(weights))
+}
+
+/// [`ewm_mean`] of run-length encoded input, where `values[i]` is repeated
+/// `run_lengths[i]` times.
+///
+/// A run of `k` equal values decays the previous weights by `(1 - alpha)^k` and adds
+/// `1 - (1 - alpha)^k` of weight at once, so the cost is per run rather than per
+/// element. The output has one value per run: the mean at the end of that run.
+///
+/// # Panics
+/// Panics if `values` and `run_lengths` differ in length.
+pub fn ewm_mean_rle<T>(
+    values: &[T],
+    run_lengths: &[usize],
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    T: Float + NativeType + AddAssign,
+{
+    assert_eq!(
+        values.len(),
+        run_lengths.len(),
+        "values and run_lengths must have equal length"
+    );
+    let one_sub_alpha = T::one() - alpha;
+    let mut opt_mean: Option<T> = None;
+    let mut non_null_cnt = 0usize;
+    let mut wgt_sum = if adjust { T::zero() } else { T::one() };
+
+    values
+        .iter()
+        .zip(run_lengths)
+        .map(|(&x, &run_length)| {
+            if run_length > 0 {
+                non_null_cnt += run_length;
+
+                let decay = one_sub_alpha.powf(T::from(run_length).unwrap());
+                let prev_mean = opt_mean.unwrap_or(x);
+                wgt_sum = decay * wgt_sum + (T::one() - decay);
+                opt_mean = Some(prev_mean + (x - prev_mean) * (T::one() - decay) / wgt_sum);
+            }
+            match non_null_cnt < min_periods {
+                true => None,
+                false => opt_mean,
+            }
+        })
+        .collect_ewm()
+}
+
+/// The residual `x - ewm_mean` of every observation, computed in the same pass as the
+/// [`ewm_mean`]. Null where the input or the mean is null.
+pub fn ewm_residual<I, T>(xs: I, alpha: T, adjust: bool, min_periods: usize) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    xs.into_iter()
+        .map(|opt_x| {
+            let opt_mean = state.update(opt_x);
+            Some(opt_x? - opt_mean?)
+        })
+        .collect_ewm()
+}
+
+/// Compute the [`ewm_mean`] together with its velocity, the difference of each output
+/// to the previous one.
+///
+/// The velocity is null at the first position and wherever either output is null.
+pub fn ewm_mean_and_velocity<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> (PrimitiveArray<T>, PrimitiveArray<T>)
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    let mut velocity = Vec::with_capacity(xs.size_hint().0);
+
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    let mut opt_prev = None;
+
+    let means = xs
+        .map(|opt_x| {
+            let opt_mean = state.update(opt_x);
+            velocity.push(opt_mean.zip(opt_prev).map(|(mean, prev)| mean - prev));
+            opt_prev = opt_mean;
+            opt_mean
+        })
+        .collect_ewm();
+    (means, PrimitiveArray::from(velocity))
+}
+
+/// [`ewm_mean`] that recomputes the weight sum from its closed form every
+/// `renorm_every` observations.
+///
+/// The incremental update of the weight sum rounds at every step. On very long inputs
+/// resetting it to `1 - (1 - alpha)^n` (or `1` without `adjust`) keeps these rounding
+/// errors from building up. `None` never renormalizes.
+///
+/// # Panics
+/// Panics if `renorm_every == Some(0)`.
+pub fn ewm_mean_with_renorm<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    renorm_every: Option<usize>,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    assert_ne!(renorm_every, Some(0), "renorm_every must be positive");
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    let mut non_null_cnt = 0usize;
+
+    xs.into_iter()
+        .map(|opt_x| {
+            let out = state.update(opt_x);
+            non_null_cnt += opt_x.is_some() as usize;
+            if matches!(renorm_every, Some(n) if opt_x.is_some() && non_null_cnt % n == 0) {
+                state.renormalize();
+            }
+            out
+        })
+        .collect_ewm()
+}
+
+/// [`ewm_mean`] of the [`ewm_mean`], computed in a single pass, e.g. for TRIX-style
+/// indicators.
+///
+/// The second average with `alpha2` consumes every output of the first one with
+/// `alpha1`, so this equals two sequential [`ewm_mean`] calls sharing `adjust` and
+/// `min_periods`.
+pub fn ewm_mean_double<I, T>(
+    xs: I,
+    alpha1: T,
+    alpha2: T,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut first = EwmMeanState::new(alpha1, adjust, min_periods);
+    let mut second = EwmMeanState::new(alpha2, adjust, min_periods);
+    xs.into_iter()
+        .map(|opt_x| second.update(first.update(opt_x)))
+        .collect_ewm()
+}
+
+/// [`ewm_mean`] that can set its first valid output to null.
+///
+/// The first valid output is the first observation itself once `min_periods=1`, a
+/// "perfect fit" that can be misleading when evaluating the smoother. With
+/// `drop_first_valid` it is null; every other output is unchanged.
+pub fn ewm_mean_drop_first<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    drop_first_valid: bool,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut dropped = !drop_first_valid;
+    ewm_mean_iter(xs, alpha, adjust, min_periods)
+        .map(|opt_mean| match opt_mean {
+            Some(_) if !dropped => {
+                dropped = true;
+                None
+            }
+            _ => opt_mean,
+        })
+        .collect_ewm()
+}
+
+/// How [`ewm_mean_with_null_handling`] treats null observations.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum NullHandling {
+    /// Skip nulls, as [`ewm_mean`] does; the weights do not decay over them.
+    #[default]
+    Ignore,
+    /// Replace a null with the last observation.
+    ForwardFill,
+    /// Replace a null with the current mean, so the mean is unchanged but the weights
+    /// of older observations decay over the gap.
+    ///
+    /// With `adjust=false` the weights are normalized at every step and nothing decays,
+    /// so this is the same as [`NullHandling::Ignore`].
+    ImputeMean,
+}
+
+/// [`ewm_mean`] with a configurable treatment of nulls, see [`NullHandling`].
+///
+/// Imputed observations do not count towards `min_periods`, forward filled ones do.
+pub fn ewm_mean_with_null_handling<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    null_handling: NullHandling,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    let mut opt_last = None;
+
+    xs.into_iter()
+        .map(|opt_x| match (opt_x, null_handling) {
+            (Some(_), _) => {
+                opt_last = opt_x;
+                state.update(opt_x)
+            }
+            (None, NullHandling::Ignore) => state.update(None),
+            (None, NullHandling::ForwardFill) => state.update(opt_last),
+            (None, NullHandling::ImputeMean) => {
+                state.decay();
+                state.mean()
+            }
+        })
+        .collect_ewm()
+}
+
+/// How [`ewm_mean_with_nan_policy`] treats `NaN` observations.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum NanPolicy {
+    /// Every output from the first `NaN` on is `NaN`, as in numpy. [`ewm_mean`] does
+    /// the same, except with `alpha=1`, where the mean recovers at the next value.
+    #[default]
+    Propagate,
+    /// Treat a `NaN` like a null.
+    Null,
+}
+
+/// [`ewm_mean`] with a configurable treatment of `NaN`, see [`NanPolicy`].
+///
+/// Under [`NanPolicy::Propagate`] the outputs that would be null are still null.
+pub fn ewm_mean_with_nan_policy<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    nan_policy: NanPolicy,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let float_mode = FloatMode {
+        nan_policy,
+        ..Default::default()
+    };
+    ewm_mean_with_float_mode(xs, alpha, adjust, min_periods, float_mode)
+}
+
+/// How [`ewm_mean_with_float_mode`] treats infinite observations.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum InfPolicy {
+    /// Feed infinities to the mean, as [`ewm_mean`] does.
+    #[default]
+    Keep,
+    /// Treat an infinity like a null.
+    Null,
+}
+
+/// The treatment of non-normal floats by [`ewm_mean_with_float_mode`]. The default
+/// matches [`ewm_mean`], up to `alpha=1` (see [`NanPolicy::Propagate`]).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct FloatMode {
+    /// Flush subnormal inputs and outputs to a zero of the same sign.
+    pub flush_denormals: bool,
+    pub nan_policy: NanPolicy,
+    pub inf_policy: InfPolicy,
+}
+
+/// [`ewm_mean`] with a configurable treatment of subnormals, `NaN` and infinities, see
+/// [`FloatMode`].
+pub fn ewm_mean_with_float_mode<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    float_mode: FloatMode,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let flush = |x: T| match float_mode.flush_denormals && x.is_subnormal() {
+        true => x.signum() * T::zero(),
+        false => x,
+    };
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    let mut poisoned = false;
+
+    xs.into_iter()
+        .map(|opt_x| {
+            let opt_x = match opt_x {
+                Some(x) if x.is_nan() && float_mode.nan_policy == NanPolicy::Null => None,
+                Some(x) if x.is_infinite() && float_mode.inf_policy == InfPolicy::Null => None,
+                _ => opt_x.map(flush),
+            };
+            // only reachable with `NanPolicy::Propagate`
+            poisoned |= matches!(opt_x, Some(x) if x.is_nan());
+            match poisoned {
+                true => state.update(opt_x).map(|_| T::nan()),
+                false => state.update(opt_x).map(flush),
+            }
+        })
+        .collect_ewm()
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::array::Array;
+    use arrow::datatypes::DataType;
+
+    use super::*;
+    use crate::kernels::ewm::ewm_assert_close;
+
+    #[test]
+    fn test_ewm_mean_without_null() {
+        let xs = vec![Some(1.0f32), Some(2.0f32), Some(3.0f32)];
+
+        for adjust in [false, true] {
+            let result = ewm_mean(xs.clone().into_iter(), 0.5, adjust, 0);
+
+            let expected = match adjust {
+                false => PrimitiveArray::from([Some(1.0f32), Some(1.5f32), Some(2.25f32)]),
+                true => PrimitiveArray::from([
+                    Some(1.0f32),
+                    Some(1.6666667f32), // <-- pandas: 1.66666667
+                    Some(2.42857143),
+                ]),
+            };
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_with_null() {
+        let xs = vec![Some(1.0f32), None, Some(1.0f32), Some(1.0f32)].into_iter();
+        let result = ewm_mean(xs, 0.5, false, 2);
+        let expected = PrimitiveArray::from([None, None, Some(1.0f32), Some(1.0f32)]);
+        assert_eq!(result, expected);
+
+        let xs = vec![None, None, Some(1.0f32), Some(1.0f32)].into_iter();
+        let result = ewm_mean(xs, 0.5, false, 1);
+        let expected = PrimitiveArray::from([None, None, Some(1.0f32), Some(1.0f32)]);
+        assert_eq!(result, expected);
+
+        let xs = vec![
+            Some(2.0f32),
+            Some(3.0f32),
+            Some(5.0f32),
+            Some(7.0f32),
+            None,
+            None,
+            None,
+            Some(4.0f32),
+        ];
+        let result = ewm_mean(xs, 0.5, false, 0);
+        let expected = PrimitiveArray::from([
+            Some(2.0f32),
+            Some(2.5f32),
+            Some(3.75f32),
+            Some(5.375f32),
+            Some(5.375f32),
+            Some(5.375f32),
+            Some(5.375f32),
+            Some(4.6875f32),
+        ]);
+        assert_eq!(result, expected);
+
+        let xs = vec![
+            None,
+            None,
+            Some(5.0f32),
+            Some(7.0f32),
+            None,
+            Some(2.0f32),
+            Some(1.0f32),
+            Some(4.0f32),
+        ];
+        let unadjusted_result = ewm_mean(xs.clone().into_iter(), 0.5, false, 1);
+        let unadjusted_expected = PrimitiveArray::from([
+            None,
+            None,
+            Some(5.0f32),
+            Some(6.0f32),
+            Some(6.0f32),
+            Some(4.0f32),
+            Some(2.5f32),
+            Some(3.25f32),
+        ]);
+        assert_eq!(unadjusted_result, unadjusted_expected);
+        let adjusted_result = ewm_mean(xs.clone().into_iter(), 0.5, true, 1);
+        let adjusted_expected = PrimitiveArray::from([
+            None,
+            None,
+            Some(5.0f32),
+            Some(6.33333333f32),
+            Some(6.33333333f32),
+            Some(3.85714286f32),
+            Some(2.3333335f32), // <-- pandas: 2.33333333
+            Some(3.19354839f32),
+        ]);
+        assert_eq!(adjusted_result, adjusted_expected);
+
+        let xs = vec![
+            None,
+            Some(1.0f32),
+            Some(5.0f32),
+            Some(7.0f32),
+            None,
+            Some(2.0f32),
+            Some(1.0f32),
+            Some(4.0f32),
+        ]
+        .into_iter();
+        let result = ewm_mean(xs, 0.5, true, 1);
+        let expected = PrimitiveArray::from([
+            None,
+            Some(1.0f32),
+            Some(3.66666667f32),
+            Some(5.57142857f32),
+            Some(5.57142857f32),
+            Some(3.66666667),
+            Some(2.2903228f32), // <-- pandas: 2.29032258
+            Some(3.15873016f32),
+        ]);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_ewm_mean_soa() {
+        let values = [2.0f64, 0.0, 5.0, 7.0, 0.0, 2.0];
+        let valid = [true, false, true, true, false, true];
+        let xs = values
+            .iter()
+            .zip(valid)
+            .map(|(&x, is_valid)| is_valid.then_some(x))
+            .collect::<Vec<_>>();
+
+        for adjust in [false, true] {
+            let result = ewm_mean_soa(&values, &valid, 0.5, adjust, 1);
+            let expected = ewm_mean(xs.clone(), 0.5, adjust, 1);
+            assert_eq!(result, expected);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_ewm_mean_soa_length_mismatch() {
+        ewm_mean_soa(&[1.0f64, 2.0], &[true], 0.5, true, 1);
+    }
+
+    #[test]
+    fn test_ewm_mean_burn_in() {
+        let xs = vec![
+            Some(100.0f64),
+            None,
+            Some(-50.0),
+            Some(3.0),
+            None,
+            Some(4.0),
+            Some(1.0),
+        ];
+        for adjust in [false, true] {
+            let result = ewm_mean_burn_in(xs.clone(), 0.5, adjust, 1, 2);
+            let dropped = ewm_mean(xs[3..].to_vec(), 0.5, adjust, 1);
+
+            assert!(result.iter().take(3).all(|v| v.is_none()));
+            assert!(result.iter().skip(3).eq(dropped.iter()));
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_downsampled() {
+        let xs = vec![
+            Some(2.0f64),
+            None,
+            Some(5.0),
+            Some(7.0),
+            None,
+            Some(2.0),
+            Some(1.0),
+            Some(4.0),
+        ];
+        for (alpha, adjust) in [(0.5, false), (0.5, true), (1.0, true)] {
+            let full = ewm_mean(xs.clone(), alpha, adjust, 2);
+            for stride in [1, 3, 8] {
+                let result = ewm_mean_downsampled(xs.clone(), alpha, adjust, 2, stride);
+                assert_eq!(result.len(), full.iter().step_by(stride).count());
+                assert!(result.iter().eq(full.iter().step_by(stride)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_masked() {
+        let xs = vec![
+            Some(1.0f64),
+            Some(100.0),
+            Some(3.0),
+            Some(100.0),
+            None,
+            Some(100.0),
+            Some(5.0),
+        ];
+        let mask = BooleanArray::from_slice([true, false, true, false, true, false, true]);
+        let selected = vec![Some(1.0f64), Some(3.0), None, Some(5.0)];
+
+        for adjust in [false, true] {
+            let result = ewm_mean_masked(xs.clone(), &mask, 0.5, adjust, 1);
+            let expected = ewm_mean(selected.clone(), 0.5, adjust, 1);
+
+            assert!(result.iter().skip(1).step_by(2).all(|v| v.is_none()));
+            assert!(result.iter().step_by(2).eq(expected.iter()));
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_with_null_reset() {
+        let xs = vec![
+            Some(2.0f64),
+            Some(4.0),
+            None,
+            Some(6.0),
+            None,
+            None,
+            Some(10.0),
+            Some(8.0),
+        ];
+        let arr = PrimitiveArray::from(xs.clone());
+        let result = ewm_mean_with_null_reset(&arr, 0.5, false, 1, Some(2));
+        let expected = PrimitiveArray::from([
+            Some(2.0),
+            Some(3.0),
+            Some(3.0),
+            Some(4.5),
+            Some(4.5),
+            None,
+            Some(10.0),
+            Some(9.0),
+        ]);
+        assert_eq!(result, expected);
+
+        let result = ewm_mean_with_null_reset(&arr, 0.5, true, 1, None);
+        assert_eq!(result, ewm_mean(xs, 0.5, true, 1));
+
+        // runs of nulls across the words of the validity bitmap
+        let xs = (0..400)
+            .map(|i| (i % 97 > 70 || i % 5 == 0).then_some((i % 13) as f64))
+            .collect::<Vec<_>>();
+        let arr = PrimitiveArray::from(xs.clone());
+        for max_nulls in [0, 1, 3, 20] {
+            let mut state = EwmMeanState::new(0.3, true, 2);
+            let mut null_run = 0;
+            let expected = xs
+                .iter()
+                .map(|&opt_x| {
+                    null_run = if opt_x.is_some() { 0 } else { null_run + 1 };
+                    if null_run >= max_nulls && null_run > 0 {
+                        state.reset();
+                    }
+                    state.update(opt_x)
+                })
+                .collect::<PrimitiveArray<_>>();
+            let result = ewm_mean_with_null_reset(&arr, 0.3, true, 2, Some(max_nulls));
+            assert_eq!(result, expected);
+        }
+
+        // the data type is kept with and without nulls
+        let data_type = DataType::Extension("ext".to_string(), Box::new(DataType::Float64), None);
+        for xs in [vec![Some(1.0f64), Some(2.0)], vec![Some(1.0), None]] {
+            let arr = PrimitiveArray::from(xs).to(data_type.clone());
+            let result = ewm_mean_with_null_reset(&arr, 0.5, true, 1, Some(1));
+            assert_eq!(result.data_type(), &data_type);
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_cross_section() {
+        let row = [Some(2.0f64), Some(4.0), None, Some(6.0), Some(8.0)];
+        let result = ewm_mean_cross_section(&row, 0.5, false, 1);
+        let expected =
+            PrimitiveArray::from([Some(2.0), Some(3.0), Some(3.0), Some(4.5), Some(6.25)]);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_ewm_mean_with_sign_reset() {
+        let xs = vec![
+            Some(2.0f64),
+            Some(4.0),
+            Some(-1.0),
+            None,
+            Some(-3.0),
+            Some(0.0),
+            Some(8.0),
+        ];
+        let result = ewm_mean_with_sign_reset(xs.clone(), 0.5, false, 1, true);
+        let expected = PrimitiveArray::from([
+            Some(2.0),
+            Some(3.0),
+            Some(-1.0),
+            Some(-1.0),
+            Some(-2.0),
+            Some(-1.0),
+            Some(8.0),
+        ]);
+        assert_eq!(result, expected);
+
+        let result = ewm_mean_with_sign_reset(xs.clone(), 0.5, false, 1, false);
+        assert_eq!(result, ewm_mean(xs, 0.5, false, 1));
+    }
+
+    #[test]
+    fn test_ewm_mean_reverse() {
+        let xs = [Some(1.0f64), None, Some(3.0), Some(5.0), None];
+        let result = ewm_mean_reverse(&xs, 0.5, true, 1);
+
+        let reversed = xs.iter().rev().copied().collect::<Vec<_>>();
+        let mut expected = ewm_mean(reversed, 0.5, true, 1)
+            .iter()
+            .map(|v| v.copied())
+            .collect::<Vec<_>>();
+        expected.reverse();
+        assert_eq!(result, PrimitiveArray::from(expected));
+        assert!(result.iter().next_back().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_ewm_mean_with_deadband() {
+        let xs = vec![
+            Some(10.0f64),
+            Some(10.2),
+            Some(9.9),
+            None,
+            Some(10.1),
+            Some(9.8),
+            Some(12.0),
+        ];
+        let (result, updated) = ewm_mean_with_deadband(xs.clone(), 0.5, false, 1, Some(0.25));
+        let expected = PrimitiveArray::from([
+            Some(10.0),
+            Some(10.0),
+            Some(10.0),
+            Some(10.0),
+            Some(10.0),
+            Some(10.0),
+            Some(11.0),
+        ]);
+        assert_eq!(result, expected);
+        let expected = BooleanArray::from([
+            Some(true),
+            Some(false),
+            Some(false),
+            None,
+            Some(false),
+            Some(false),
+            Some(true),
+        ]);
+        assert_eq!(updated, expected);
+
+        let (result, _) = ewm_mean_with_deadband(xs.clone(), 0.5, false, 1, None);
+        assert_eq!(result, ewm_mean(xs.clone(), 0.5, false, 1));
+
+        // during the warm-up of `min_periods=3` 10.2 updates the mean, 9.9 completes it
+        let (result, updated) = ewm_mean_with_deadband(xs, 0.5, false, 3, Some(0.25));
+        assert_eq!(result.iter().take(2).flatten().count(), 0);
+        assert!((result.value(2) - 10.0).abs() < 1e-12);
+        let expected = [Some(true), Some(true), Some(true), None, Some(false)];
+        assert_eq!(updated.iter().take(5).collect::<Vec<_>>(), expected);
     }
 
-    let one_sub_alpha = T::one() - alpha;
+    #[test]
+    fn test_ewm_mean_reflect_start() {
+        // oscillates around a level of 2
+        let xs = (0..40)
+            .map(|i| Some(if i % 2 == 0 { 4.0f64 } else { 0.0 }))
+            .collect::<Vec<_>>();
+        let plain = ewm_mean(xs.clone(), 0.2, false, 1);
+        let corrected = ewm_mean_reflect_start(xs.clone(), 0.2, 4, false, 1);
+
+        for i in 0..4 {
+            assert!((corrected.value(i) - 2.0).abs() < (plain.value(i) - 2.0).abs());
+        }
+        assert!((corrected.value(39) - plain.value(39)).abs() < 1e-3);
 
-    let mut opt_mean = None;
-    let mut non_null_cnt = 0usize;
+        let corrected = ewm_mean_reflect_start(xs.clone(), 0.2, 0, false, 1);
+        assert_eq!(corrected, plain);
+    }
 
-    let wgt = alpha;
-    let mut wgt_sum = if adjust { T::zero() } else { T::one() };
+    #[test]
+    fn test_ewm_mean_with_sink() {
+        let xs = vec![None, Some(1.0f64), Some(5.0), None, Some(2.0), Some(9.0)];
+        let mut buckets = [0usize; 4];
+        let result = ewm_mean_with_sink(xs.clone(), 0.5, false, 2, |mean| {
+            buckets[(mean / 2.5) as usize] += 1
+        });
 
-    xs.into_iter()
-        .map(|opt_x| {
-            if let Some(x) = opt_x {
-                non_null_cnt += 1;
+        assert_eq!(result, ewm_mean(xs, 0.5, false, 2));
+        // outputs are [null, null, 3.0, 3.0, 2.5, 5.75]
+        assert_eq!(buckets, [0, 3, 1, 0]);
+    }
 
-                let prev_mean = opt_mean.unwrap_or(x);
+    #[test]
+    fn test_ewm_mean_subnormal() {
+        // 2^-1070, well below f64::MIN_POSITIVE
+        let tiny = f64::from_bits(1 << 4);
+        assert!(tiny.is_subnormal());
 
-                wgt_sum = one_sub_alpha * wgt_sum + wgt;
+        let units = vec![Some(8.0f64), Some(4.0), None, Some(2.0), Some(6.0)];
+        let xs = units
+            .iter()
+            .map(|x| x.map(|x| x * tiny))
+            .collect::<Vec<_>>();
 
-                let curr_mean = prev_mean + (x - prev_mean) * wgt / wgt_sum;
+        // dyadic weights keep the recurrence exact, even with reduced precision
+        let result = ewm_mean(xs.clone(), 0.5, false, 1);
+        let expected = ewm_mean(units.clone(), 0.5, false, 1);
+        assert!(result
+            .iter()
+            .zip(expected.iter())
+            .all(|(a, b)| a.copied() == b.map(|b| b * tiny)));
 
-                opt_mean = Some(curr_mean);
-            }
-            match non_null_cnt < min_periods {
-                true => None,
-                false => opt_mean,
-            }
-        })
-        .collect_trusted()
-}
+        // otherwise the result is within the subnormal resolution of the scaled output
+        let resolution = f64::from_bits(1);
+        let result = ewm_mean(xs, 0.3, true, 1);
+        let expected = ewm_mean(units, 0.3, true, 1);
+        for (a, b) in result.values().iter().zip(expected.values().iter()) {
+            assert!(*a > 0.0 && a.is_subnormal());
+            assert!((a - b * tiny).abs() <= 4.0 * resolution);
+        }
+    }
 
-/// To prevent numerical instability (and as a slight optimization), we
-/// special-case ``alpha=1``.
-fn ewm_mean_alpha_equals_one<I, T>(xs: I, min_periods: usize) -> PrimitiveArray<T>
-where
-    I: IntoIterator<Item = Option<T>>,
-    I::IntoIter: TrustedLen,
-    T: Float + NativeType + AddAssign,
-{
-    let mut non_null_count = 0usize;
-    xs.into_iter()
-        .map(|opt_x| {
-            if opt_x.is_some() {
-                non_null_count += 1;
-            }
-            match non_null_count < min_periods {
-                true => None,
-                false => opt_x,
+    #[test]
+    fn test_ewm_mean_first_value_exact() {
+        for x in [0.1f64, -7.3, 1e300, 1.0 / 3.0] {
+            for alpha in [0.0, 0.3, 0.5, 0.999] {
+                let result = ewm_mean(vec![None, Some(x), Some(1.0)], alpha, true, 1);
+                assert_eq!(result.value(1), x);
             }
-        })
-        .collect_trusted()
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
+        }
+        // without weight, later observations leave the mean untouched
+        let result = ewm_mean(vec![Some(2.0f64), Some(5.0)], 0.0, true, 1);
+        assert_eq!(result, PrimitiveArray::from([Some(2.0), Some(2.0)]));
+    }
 
     #[test]
-    fn test_ewm_mean_without_null() {
-        let xs = vec![Some(1.0f32), Some(2.0f32), Some(3.0f32)];
+    fn test_ewm_mean_reset_on() {
+        let xs = vec![
+            Some(2.0f64),
+            Some(4.0),
+            Some(-999.0),
+            None,
+            Some(10.0),
+            Some(8.0),
+        ];
+        let result = ewm_mean_reset_on(xs, -999.0, 0.5, false, 1);
+        let expected =
+            PrimitiveArray::from([Some(2.0), Some(3.0), None, None, Some(10.0), Some(9.0)]);
+        assert_eq!(result, expected);
+
+        let xs = vec![Some(2.0f64), Some(f64::NAN), Some(6.0)];
+        let result = ewm_mean_reset_on(xs, f64::NAN, 0.5, false, 1);
+        assert_eq!(result, PrimitiveArray::from([Some(2.0), None, Some(6.0)]));
+    }
 
+    #[test]
+    fn test_ewm_accumulators() {
+        let xs = vec![None, Some(1.0f64), Some(5.0), None, Some(2.0), Some(-3.0)];
         for adjust in [false, true] {
-            let result = ewm_mean(xs.clone().into_iter(), 0.5, adjust, 0);
+            let (sums, weights) = ewm_accumulators(xs.clone(), 0.3, adjust);
+            let expected = ewm_mean(xs.clone(), 0.3, adjust, 1);
 
-            let expected = match adjust {
-                false => PrimitiveArray::from([Some(1.0f32), Some(1.5f32), Some(2.25f32)]),
-                true => PrimitiveArray::from([
-                    Some(1.0f32),
-                    Some(1.6666667f32), // <-- pandas: 1.66666667
-                    Some(2.42857143),
-                ]),
-            };
-            assert_eq!(result, expected);
+            assert!(sums.iter().next().unwrap().is_none());
+            for ((s, w), mean) in sums.iter().zip(weights.iter()).zip(expected.iter()) {
+                match (s, w, mean) {
+                    (Some(s), Some(w), Some(mean)) => assert!((s / w - mean).abs() < 1e-12),
+                    (None, None, None) => {}
+                    _ => panic!("validity of the accumulators differs from ewm_mean"),
+                }
+            }
         }
     }
 
     #[test]
-    fn test_ewm_mean_with_null() {
-        let xs = vec![Some(1.0f32), None, Some(1.0f32), Some(1.0f32)].into_iter();
-        let result = ewm_mean(xs, 0.5, false, 2);
-        let expected = PrimitiveArray::from([None, None, Some(1.0f32), Some(1.0f32)]);
-        assert_eq!(result, expected);
-
-        let xs = vec![None, None, Some(1.0f32), Some(1.0f32)].into_iter();
-        let result = ewm_mean(xs, 0.5, false, 1);
-        let expected = PrimitiveArray::from([None, None, Some(1.0f32), Some(1.0f32)]);
-        assert_eq!(result, expected);
-
+    fn test_ewm_mean_block_average() {
         let xs = vec![
-            Some(2.0f32),
-            Some(3.0f32),
-            Some(5.0f32),
-            Some(7.0f32),
             None,
             None,
+            Some(2.0f64),
+            Some(5.0),
             None,
-            Some(4.0f32),
+            Some(1.0),
+            Some(4.0),
         ];
-        let result = ewm_mean(xs, 0.5, false, 0);
-        let expected = PrimitiveArray::from([
-            Some(2.0f32),
-            Some(2.5f32),
-            Some(3.75f32),
-            Some(5.375f32),
-            Some(5.375f32),
-            Some(5.375f32),
-            Some(5.375f32),
-            Some(4.6875f32),
-        ]);
+        let full = ewm_mean(xs.clone(), 0.5, true, 1);
+        let full = full.iter().map(|v| v.copied()).collect::<Vec<_>>();
+        let expected = full
+            .chunks(2)
+            .map(|chunk| {
+                let valid = chunk.iter().flatten().collect::<Vec<_>>();
+                (!valid.is_empty()).then(|| valid.iter().copied().sum::<f64>() / valid.len() as f64)
+            })
+            .collect::<Vec<_>>();
+
+        let result = ewm_mean_block_average(xs, 0.5, 2, true, 1);
+        assert_eq!(result, PrimitiveArray::from(expected));
+        assert_eq!(result.len(), 4);
+        assert!(result.iter().next().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_try_ewm_mean() {
+        let xs = ["1.0", "", "3.5", "2.0"];
+        let parse = |s: &&str| match s.is_empty() {
+            true => Ok(None),
+            false => s.parse::<f64>().map(Some),
+        };
+
+        let result = try_ewm_mean(xs.iter().map(parse), 0.5, true, 1).unwrap();
+        let expected = ewm_mean(
+            xs.iter().map(|s| parse(s).unwrap()).collect::<Vec<_>>(),
+            0.5,
+            true,
+            1,
+        );
         assert_eq!(result, expected);
 
+        let xs = ["1.0", "oops", "3.5"];
+        let mut consumed = 0;
+        let result = try_ewm_mean(
+            xs.iter().inspect(|_| consumed += 1).map(parse),
+            0.5,
+            true,
+            1,
+        );
+        assert!(result.is_err());
+        assert_eq!(consumed, 2);
+    }
+
+    #[test]
+    fn test_ewm_mean_with_null_handling() {
+        let xs = vec![Some(4.0f64), Some(2.0), None, None, Some(8.0)];
+        let run =
+            |null_handling| ewm_mean_with_null_handling(xs.clone(), 0.5, true, 1, null_handling);
+
+        assert_eq!(
+            run(NullHandling::Ignore),
+            ewm_mean(xs.clone(), 0.5, true, 1)
+        );
+
+        // the weights of 4 and 2 are 1/16 and 1/8 when 8 arrives, 3 and 2 are imputed
+        // with 1/4 and 1/2 - 8 enters with 1
+        let imputed = 8.0 / 3.0;
+        let expected = (4.0 / 16.0 + 2.0 / 8.0 + imputed * (1.0 / 4.0 + 1.0 / 2.0) + 8.0)
+            / (1.0 / 16.0 + 1.0 / 8.0 + 1.0 / 4.0 + 1.0 / 2.0 + 1.0);
+        let result = run(NullHandling::ImputeMean);
+        assert!((result.value(1) - imputed).abs() < 1e-12);
+        assert_eq!(result.value(2), result.value(1));
+        assert_eq!(result.value(3), result.value(1));
+        assert!((result.value(4) - expected).abs() < 1e-12);
+
+        let filled = vec![Some(4.0f64), Some(2.0), Some(2.0), Some(2.0), Some(8.0)];
+        let result = run(NullHandling::ForwardFill);
+        assert_eq!(result, ewm_mean(filled, 0.5, true, 1));
+
+        // all three differ on the final value
+        let last = [
+            NullHandling::Ignore,
+            NullHandling::ForwardFill,
+            NullHandling::ImputeMean,
+        ]
+        .map(|null_handling| run(null_handling).value(4));
+        assert!(last[0] != last[1] && last[1] != last[2] && last[0] != last[2]);
+
+        // without adjustment there is nothing to decay
+        assert_eq!(
+            ewm_mean_with_null_handling(xs.clone(), 0.5, false, 1, NullHandling::ImputeMean),
+            ewm_mean(xs, 0.5, false, 1)
+        );
+    }
+
+    #[test]
+    fn test_ewm_mean_rle() {
+        let values = [2.0f64, -1.0, 5.0, 3.0, 0.5];
+        let run_lengths = [3, 1, 0, 4, 2];
+        let expanded = values
+            .iter()
+            .zip(run_lengths)
+            .flat_map(|(&x, len)| vec![Some(x); len])
+            .collect::<Vec<_>>();
+
+        for adjust in [false, true] {
+            let result = ewm_mean_rle(&values, &run_lengths, 0.3, adjust, 5);
+            let expected = ewm_mean(expanded.clone(), 0.3, adjust, 5);
+
+            let mut end = 0;
+            for (i, len) in run_lengths.iter().enumerate() {
+                end += len;
+                match (
+                    result.iter().nth(i).unwrap(),
+                    expected.iter().nth(end - 1).unwrap(),
+                ) {
+                    (Some(a), Some(b)) => assert!((a - b).abs() < 1e-12, "{a} != {b}"),
+                    (a, b) => assert_eq!(a, b),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_ewm_residual() {
+        let xs = vec![Some(1.5f64), None, Some(-2.0), Some(7.25), None, Some(3.0)];
+        let means = ewm_mean(xs.clone(), 0.4, true, 2);
+        let residuals = ewm_residual(xs.clone(), 0.4, true, 2);
+
+        assert_eq!(
+            residuals.iter().map(|r| r.is_some()).collect::<Vec<_>>(),
+            [false, false, true, true, false, true]
+        );
+        for ((x, mean), residual) in xs.iter().zip(means.iter()).zip(residuals.iter()) {
+            if let Some(residual) = residual {
+                assert!((residual + mean.unwrap() - x.unwrap()).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_and_velocity() {
+        let xs = (0..100).map(|i| Some(0.5 * i as f64)).collect::<Vec<_>>();
+        let (means, velocity) = ewm_mean_and_velocity(xs.clone(), 0.2, true, 1);
+
+        assert_eq!(means, ewm_mean(xs, 0.2, true, 1));
+        assert_eq!(velocity.iter().next().unwrap(), None);
+        // the mean lags behind the ramp by a constant, so it moves with the ramp's slope
+        assert!((velocity.value(99) - 0.5).abs() < 1e-6);
+
+        let (_, velocity) =
+            ewm_mean_and_velocity(vec![None, Some(1.0f64), Some(2.0)], 0.5, false, 1);
+        assert_eq!(velocity, PrimitiveArray::from([None, None, Some(0.5)]));
+    }
+
+    #[test]
+    fn test_ewm_mean_with_renorm() {
+        let xs = (0..200_000)
+            .map(|i| Some(((i * 7919) % 1000) as f32 / 100.0))
+            .collect::<Vec<_>>();
+        let reference = ewm_mean(xs.iter().map(|x| x.map(f64::from)), 0.05, true, 1);
+
+        let error = |renorm_every| {
+            let result = ewm_mean_with_renorm(xs.clone(), 0.05f32, true, 1, renorm_every);
+            result
+                .values()
+                .iter()
+                .zip(reference.values().iter())
+                .map(|(a, b)| (f64::from(*a) - b).abs())
+                .sum::<f64>()
+        };
+        assert!(error(Some(100)) < error(None));
+        assert_eq!(
+            ewm_mean_with_renorm(xs.clone(), 0.05f32, true, 1, None),
+            ewm_mean(xs, 0.05f32, true, 1)
+        );
+
+        let xs = vec![Some(1.0f64), None, Some(3.0), Some(2.0)];
+        for (alpha, adjust) in [(1.0, true), (1.0, false), (0.5, false)] {
+            assert_eq!(
+                ewm_mean_with_renorm(xs.clone(), alpha, adjust, 1, Some(1)),
+                ewm_mean(xs.clone(), alpha, adjust, 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_double() {
         let xs = vec![
             None,
+            Some(2.0f64),
+            Some(-1.0),
             None,
-            Some(5.0f32),
-            Some(7.0f32),
+            Some(4.0),
+            Some(0.5),
+            Some(3.0),
+        ];
+        for (adjust, min_periods) in [(false, 1), (true, 1), (true, 3)] {
+            let once = ewm_mean(xs.clone(), 0.3, adjust, min_periods);
+            let twice = ewm_mean(once.iter().map(|v| v.copied()), 0.6, adjust, min_periods);
+            assert_eq!(
+                ewm_mean_double(xs.clone(), 0.3, 0.6, adjust, min_periods),
+                twice
+            );
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_frozen() {
+        let xs = vec![
+            Some(1.0f64),
+            Some(3.0),
+            Some(100.0),
             None,
-            Some(2.0f32),
-            Some(1.0f32),
-            Some(4.0f32),
+            Some(-50.0),
+            Some(5.0),
         ];
-        let unadjusted_result = ewm_mean(xs.clone().into_iter(), 0.5, false, 1);
-        let unadjusted_expected = PrimitiveArray::from([
+        let freeze = Bitmap::from([false, false, true, false, true, false]);
+        for adjust in [false, true] {
+            let out = ewm_mean_frozen(xs.clone(), 0.5, adjust, 1, Some(&freeze));
+            assert_eq!(out.value(2), out.value(1));
+            assert_eq!(out.value(4), out.value(3));
+
+            // the other positions update as if the frozen ones were not there
+            let unfrozen = vec![xs[0], xs[1], xs[3], xs[5]];
+            let expected = ewm_mean(unfrozen, 0.5, adjust, 1);
+            for (i, e) in [0, 1, 3, 5].into_iter().zip(expected.values().iter()) {
+                assert_eq!(out.value(i), *e);
+            }
+
+            assert_eq!(
+                ewm_mean_frozen(xs.clone(), 0.5, adjust, 1, None),
+                ewm_mean(xs.clone(), 0.5, adjust, 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_drop_first() {
+        let xs = vec![None, Some(2.0f64), None, Some(4.0), Some(1.0)];
+        for (adjust, min_periods) in [(false, 1), (true, 1), (true, 2)] {
+            let expected = ewm_mean(xs.clone(), 0.5, adjust, min_periods);
+            let out = ewm_mean_drop_first(xs.clone(), 0.5, adjust, min_periods, true);
+
+            let first = expected.iter().position(|v| v.is_some()).unwrap();
+            assert!(out.is_null(first));
+            for i in (0..xs.len()).filter(|&i| i != first) {
+                assert_eq!(out.get(i), expected.get(i));
+            }
+            assert_eq!(
+                ewm_mean_drop_first(xs.clone(), 0.5, adjust, min_periods, false),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_paused() {
+        let xs = vec![
+            Some(1.0f64),
+            Some(3.0),
+            Some(100.0),
             None,
+            Some(-50.0),
             None,
-            Some(5.0f32),
-            Some(6.0f32),
-            Some(6.0f32),
-            Some(4.0f32),
-            Some(2.5f32),
-            Some(3.25f32),
-        ]);
-        assert_eq!(unadjusted_result, unadjusted_expected);
-        let adjusted_result = ewm_mean(xs.clone().into_iter(), 0.5, true, 1);
-        let adjusted_expected = PrimitiveArray::from([
+            Some(5.0),
+        ];
+        let paused = Bitmap::from([false, false, true, false, true, true, false]);
+        for adjust in [false, true] {
+            let out = ewm_mean_paused(xs.clone(), &paused, 0.5, adjust, 2);
+            assert_eq!(out.len(), 4);
+
+            let kept = vec![xs[0], xs[1], xs[3], xs[6]];
+            assert_eq!(out, ewm_mean(kept, 0.5, adjust, 2));
+
+            let none = Bitmap::new_zeroed(xs.len());
+            assert_eq!(
+                ewm_mean_paused(xs.clone(), &none, 0.5, adjust, 2),
+                ewm_mean(xs.clone(), 0.5, adjust, 2)
+            );
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_with_progress() {
+        let xs = (0..1000)
+            .map(|i| (i % 3 != 0).then_some(i as f64))
+            .collect::<Vec<_>>();
+        let expected = ewm_mean(xs.clone(), 0.2, true, 1);
+
+        for every in [1, 64, 999, 1000, 5000] {
+            let progress = AtomicUsize::new(10);
+            let out = ewm_mean_with_progress(xs.clone(), 0.2, true, 1, Some(&progress), every);
+            assert_eq!(out, expected);
+            assert_eq!(progress.load(Ordering::Relaxed), 1010);
+        }
+        let out = ewm_mean_with_progress(xs, 0.2, true, 1, None, 64);
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_ewm_mean_compact() {
+        let xs = vec![None, Some(2.0f64), None, None, Some(-1.0), Some(4.0), None];
+        for adjust in [false, true] {
+            let (idx, out) = ewm_mean_compact(xs.clone(), 0.3, adjust, 2);
+            assert_eq!(idx, [1, 4, 5]);
+
+            // scatter and carry forward
+            let mut full = vec![None; xs.len()];
+            for (&i, v) in idx.iter().zip(out.iter()) {
+                full[i] = v.copied();
+            }
+            for i in 1..full.len() {
+                if xs[i].is_none() {
+                    full[i] = full[i - 1];
+                }
+            }
+            assert_eq!(
+                PrimitiveArray::from(full),
+                ewm_mean(xs.clone(), 0.3, adjust, 2)
+            );
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_ratio() {
+        let num = vec![
+            Some(1.0f64),
+            Some(4.0),
             None,
+            Some(3.0),
+            Some(5.0),
+            Some(-2.0),
+        ];
+        let den = vec![
+            Some(2.0f64),
+            Some(0.0),
+            Some(1.0),
+            Some(6.0),
             None,
-            Some(5.0f32),
-            Some(6.33333333f32),
-            Some(6.33333333f32),
-            Some(3.85714286f32),
-            Some(2.3333335f32), // <-- pandas: 2.33333333
-            Some(3.19354839f32),
-        ]);
-        assert_eq!(adjusted_result, adjusted_expected);
+            Some(4.0),
+        ];
+        let ratio = vec![Some(0.5), None, None, Some(0.5), None, Some(-0.5)];
+        for adjust in [false, true] {
+            assert_eq!(
+                ewm_mean_ratio(num.clone(), den.clone(), 0.4, adjust, 1),
+                ewm_mean(ratio.clone(), 0.4, adjust, 1)
+            );
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_constant_is_exact() {
+        fn check<T: Float + NativeType + AddAssign>(c: T, alpha: T) {
+            for adjust in [false, true] {
+                let out = ewm_mean(vec![Some(c); 500], alpha, adjust, 3);
+                assert!(out.iter().take(2).all(|v| v.is_none()));
+                let bits = c.to_le_bytes();
+                assert!(out.values()[2..]
+                    .iter()
+                    .all(|v| v.to_le_bytes().as_ref() == bits.as_ref()));
+            }
+        }
+        for alpha in [1e-4, 0.01, 0.3, 0.5, 0.999, 1.0] {
+            for c in [0.1f64, -3.7, 1e300, 5e-324, 0.0, -0.0] {
+                check(c, alpha);
+            }
+            for c in [0.1f32, -3.7, 3e38, 1e-45, 0.0, -0.0] {
+                check(c, alpha as f32);
+            }
+        }
+    }
 
+    #[test]
+    fn test_ewm_mean_seeded() {
+        let xs = vec![Some(1.0f64), None, Some(3.0), Some(2.0)];
+        for adjust in [false, true] {
+            let out = ewm_mean_seeded(xs.clone(), 0.5, adjust, 1, 100.0, false);
+            assert!(out.value(0) > 3.0);
+
+            let out = ewm_mean_seeded(xs.clone(), 0.5, adjust, 1, 100.0, true);
+            let out = out.iter().map(|v| v.copied()).collect::<Vec<_>>();
+            assert_eq!(out[..2], [Some(1.0), Some(1.0)]);
+            assert!(out[2..].iter().all(|v| *v == Some(3.0)));
+
+            // a seed inside the range is not clamped
+            let out = ewm_mean_seeded(xs.clone(), 0.5, adjust, 1, 2.0, true);
+            let expected = ewm_mean_seeded(xs.clone(), 0.5, adjust, 1, 2.0, false);
+            assert_eq!(
+                out.values().as_slice()[2..],
+                expected.values().as_slice()[2..]
+            );
+        }
+        let out = ewm_mean_seeded(xs, 0.5, false, 1, 5.0, false);
+        assert_eq!(out.value(0), 3.0);
+    }
+
+    #[test]
+    fn test_checked_ewm_mean() {
+        let xs = vec![Some(1.0f64), None, Some(3.0)];
+        for alpha in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let err = checked_ewm_mean(xs.clone(), alpha, true, 1).unwrap_err();
+            assert!(err.to_string().contains("alpha must be finite"), "{err}");
+        }
+        for alpha in [0.0, -0.5, 1.5] {
+            assert!(checked_ewm_mean(xs.clone(), alpha, true, 1).is_err());
+        }
+        assert_eq!(
+            checked_ewm_mean(xs.clone(), 0.5, true, 1).unwrap(),
+            ewm_mean(xs, 0.5, true, 1)
+        );
+    }
+
+    #[test]
+    fn test_ewm_mean_with_nan_policy() {
         let xs = vec![
+            Some(1.0f64),
+            Some(2.0),
+            Some(f64::NAN),
             None,
-            Some(1.0f32),
-            Some(5.0f32),
-            Some(7.0f32),
-            None,
-            Some(2.0f32),
-            Some(1.0f32),
-            Some(4.0f32),
-        ]
-        .into_iter();
-        let result = ewm_mean(xs, 0.5, true, 1);
-        let expected = PrimitiveArray::from([
+            Some(3.0),
+            Some(4.0),
+        ];
+        for alpha in [0.5, 1.0] {
+            for adjust in [false, true] {
+                let out =
+                    ewm_mean_with_nan_policy(xs.clone(), alpha, adjust, 1, NanPolicy::Propagate);
+                assert!(out.values()[..2].iter().all(|x| !x.is_nan()));
+                // with `alpha=1` the output at the null is null
+                let after = out.iter().skip(2).flatten().collect::<Vec<_>>();
+                assert!(after.len() >= 3 && after.iter().all(|x| x.is_nan()));
+
+                let out = ewm_mean_with_nan_policy(xs.clone(), alpha, adjust, 1, NanPolicy::Null);
+                let mut nulls = xs.clone();
+                nulls[2] = None;
+                assert_eq!(out, ewm_mean(nulls, alpha, adjust, 1));
+            }
+        }
+
+        // the outputs before `min_periods` is reached stay null
+        let out = ewm_mean_with_nan_policy(xs, 0.5, true, 4, NanPolicy::Propagate);
+        assert_eq!(out.null_count(), 4);
+        assert!(out.values()[4..].iter().all(|x| x.is_nan()));
+    }
+
+    #[test]
+    fn test_ewm_mean_with_float_mode() {
+        let tiny = f64::MIN_POSITIVE / 4.0;
+        let xs = vec![
+            Some(tiny),
             None,
-            Some(1.0f32),
-            Some(3.66666667f32),
-            Some(5.57142857f32),
-            Some(5.57142857f32),
-            Some(3.66666667),
-            Some(2.2903228f32), // <-- pandas: 2.29032258
-            Some(3.15873016f32),
-        ]);
-        assert_eq!(result, expected);
+            Some(-tiny),
+            Some(1.0),
+            Some(f64::INFINITY),
+            Some(f64::NAN),
+            Some(2.0),
+        ];
+        for flush_denormals in [false, true] {
+            for nan_policy in [NanPolicy::Propagate, NanPolicy::Null] {
+                for inf_policy in [InfPolicy::Keep, InfPolicy::Null] {
+                    let float_mode = FloatMode {
+                        flush_denormals,
+                        nan_policy,
+                        inf_policy,
+                    };
+                    let out = ewm_mean_with_float_mode(xs.clone(), 0.5, true, 1, float_mode);
+
+                    // the input cleaned up by hand
+                    let cleaned = xs
+                        .iter()
+                        .map(|&opt_x| match opt_x {
+                            Some(x) if x.is_nan() && nan_policy == NanPolicy::Null => None,
+                            Some(x) if x.is_infinite() && inf_policy == InfPolicy::Null => None,
+                            Some(x) if x.is_subnormal() && flush_denormals => Some(0.0),
+                            _ => opt_x,
+                        })
+                        .collect::<Vec<_>>();
+                    let expected = ewm_mean(cleaned, 0.5, true, 1);
+                    assert_eq!(
+                        ewm_assert_close(&out, &expected, 0.0, 0.0),
+                        Ok(()),
+                        "{float_mode:?}"
+                    );
+
+                    let subnormal = out.values().iter().any(|x| x.is_subnormal());
+                    assert_eq!(subnormal, !flush_denormals, "{float_mode:?}");
+                    // the infinity turns into `NaN` at the next observation
+                    let finite = nan_policy == NanPolicy::Null && inf_policy == InfPolicy::Null;
+                    assert_eq!(out.value(6).is_finite(), finite, "{float_mode:?}");
+                }
+            }
+        }
+
+        let out = ewm_mean_with_float_mode(xs.clone(), 0.5, true, 1, FloatMode::default());
+        assert_eq!(
+            ewm_assert_close(&out, &ewm_mean(xs, 0.5, true, 1), 0.0, 0.0),
+            Ok(())
+        );
     }
 }
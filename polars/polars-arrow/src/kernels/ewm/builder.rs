@@ -0,0 +1,161 @@
+use std::fmt::{Display, Formatter};
+
+use super::{validate_alpha, EWMOptions};
+use crate::error::{PolarsError, Result};
+
+/// One way of specifying the decay of an exponentially weighted window.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Decay {
+    Alpha(f64),
+    Span(usize),
+    Com(f64),
+    HalfLife(f64),
+}
+
+impl Display for Decay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Decay::Alpha(alpha) => write!(f, "alpha={alpha}"),
+            Decay::Span(span) => write!(f, "span={span}"),
+            Decay::Com(com) => write!(f, "com={com}"),
+            Decay::HalfLife(half_life) => write!(f, "half_life={half_life}"),
+        }
+    }
+}
+
+/// Builder for [`EWMOptions`] that rejects ambiguous configurations.
+///
+/// `alpha`, `span`, `com` and `half_life` are mutually exclusive ways to set the
+/// decay. The `and_*` methods of [`EWMOptions`] silently keep the last one that was
+/// set; [`EWMOptionsBuilder::build`] returns an error if more than one was set.
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct EWMOptionsBuilder {
+    decays: Vec<Decay>,
+    options: EWMOptions,
+}
+
+impl EWMOptions {
+    pub fn builder() -> EWMOptionsBuilder {
+        EWMOptionsBuilder::default()
+    }
+}
+
+impl EWMOptionsBuilder {
+    pub fn alpha(mut self, alpha: f64) -> Self {
+        self.decays.push(Decay::Alpha(alpha));
+        self
+    }
+    pub fn span(mut self, span: usize) -> Self {
+        self.decays.push(Decay::Span(span));
+        self
+    }
+    pub fn com(mut self, com: f64) -> Self {
+        self.decays.push(Decay::Com(com));
+        self
+    }
+    pub fn half_life(mut self, half_life: f64) -> Self {
+        self.decays.push(Decay::HalfLife(half_life));
+        self
+    }
+    pub fn adjust(mut self, adjust: bool) -> Self {
+        self.options.adjust = adjust;
+        self
+    }
+    pub fn bias(mut self, bias: bool) -> Self {
+        self.options.bias = bias;
+        self
+    }
+    pub fn min_periods(mut self, min_periods: usize) -> Self {
+        self.options.min_periods = min_periods;
+        self
+    }
+    pub fn mask_output_to_input_validity(mut self, mask: bool) -> Self {
+        self.options.mask_output_to_input_validity = mask;
+        self
+    }
+
+    /// Validate the decay and create the [`EWMOptions`]. Without a decay the default
+    /// `alpha` is used.
+    pub fn build(self) -> Result<EWMOptions> {
+        let options = self.options;
+        let decay = match self.decays.as_slice() {
+            [] => return Ok(options),
+            [decay] => *decay,
+            [first, second, ..] => {
+                return Err(PolarsError::ComputeError(
+                    format!(
+                        "{first} and {second} both set the decay, only one of alpha, span, com \
+                         and half_life may be given"
+                    )
+                    .into(),
+                ))
+            }
+        };
+        // written such that `NaN` is rejected as well
+        let valid = match decay {
+            Decay::Alpha(alpha) => validate_alpha(alpha).is_ok(),
+            Decay::Span(span) => span >= 1,
+            Decay::Com(com) => com > 0.0,
+            Decay::HalfLife(half_life) => half_life > 0.0,
+        };
+        if !valid {
+            return Err(PolarsError::ComputeError(
+                format!("invalid decay: {decay}").into(),
+            ));
+        }
+        let options = match decay {
+            Decay::Alpha(alpha) => EWMOptions { alpha, ..options },
+            Decay::Span(span) => options.and_span(span),
+            Decay::Com(com) => options.and_com(com),
+            Decay::HalfLife(half_life) => options.and_half_life(half_life),
+        };
+        // e.g. `com=inf` passes the check above but gives `alpha=0`
+        validate_alpha(options.alpha).map_err(|err| {
+            PolarsError::ComputeError(format!("invalid decay: {decay}: {err}").into())
+        })?;
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ewm_options_builder() {
+        let options = EWMOptions::builder().span(3).adjust(false).build().unwrap();
+        assert_eq!(options.alpha, 0.5);
+        assert!(!options.adjust);
+        let options = EWMOptions::builder().com(1.0).build().unwrap();
+        assert_eq!(options.alpha, 0.5);
+        // the weight halves after `half_life` steps
+        let options = EWMOptions::builder().half_life(1.0).build().unwrap();
+        assert!((options.alpha - 0.5).abs() < 1e-15);
+        let options = EWMOptions::builder().half_life(5.0).build().unwrap();
+        assert!(((1.0 - options.alpha).powi(5) - 0.5).abs() < 1e-15);
+        let options = EWMOptions::builder().min_periods(4).build().unwrap();
+        assert_eq!(options.alpha, EWMOptions::default().alpha);
+        assert_eq!(options.min_periods, 4);
+
+        let err = EWMOptions::builder()
+            .alpha(0.2)
+            .span(5)
+            .build()
+            .unwrap_err();
+        assert!(err.to_string().contains("alpha=0.2 and span=5"));
+        assert!(EWMOptions::builder().alpha(0.2).alpha(0.3).build().is_err());
+
+        for builder in [
+            EWMOptions::builder().alpha(0.0),
+            EWMOptions::builder().alpha(f64::NAN),
+            EWMOptions::builder().span(0),
+            EWMOptions::builder().com(-1.0),
+            EWMOptions::builder().half_life(0.0),
+            EWMOptions::builder().com(f64::INFINITY),
+            EWMOptions::builder().half_life(f64::INFINITY),
+        ] {
+            assert!(builder.build().is_err());
+        }
+    }
+}
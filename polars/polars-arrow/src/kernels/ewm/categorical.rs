@@ -0,0 +1,72 @@
+use arrow::array::PrimitiveArray;
+
+use super::EwmMeanState;
+use crate::trusted_len::TrustedLen;
+
+/// Exponentially weighted proportion of each category in a stream of labels.
+///
+/// Every observation counts as `1` for its own category and `0` for the others, and
+/// each of these indicator series is smoothed with the [`ewm_mean`](super::ewm_mean)
+/// under `adjust=true`. The proportions therefore sum to one once a label was seen.
+/// Null labels carry the proportions forward. Returns one array per category.
+///
+/// # Panics
+/// Panics if a label is not below `n_categories`.
+pub fn ewm_category_proportions<I>(
+    labels: I,
+    n_categories: usize,
+    alpha: f64,
+) -> Vec<PrimitiveArray<f64>>
+where
+    I: IntoIterator<Item = Option<u32>>,
+    I::IntoIter: TrustedLen,
+{
+    let labels = labels.into_iter();
+    let len = labels.size_hint().0;
+
+    let mut states = vec![EwmMeanState::new(alpha, true, 1); n_categories];
+    let mut out = vec![Vec::with_capacity(len); n_categories];
+
+    for opt_label in labels {
+        if let Some(label) = opt_label {
+            assert!(
+                (label as usize) < n_categories,
+                "label {label} out of bounds for {n_categories} categories"
+            );
+        }
+        for (k, (state, proportions)) in states.iter_mut().zip(out.iter_mut()).enumerate() {
+            let opt_x = opt_label.map(|label| if label as usize == k { 1.0 } else { 0.0 });
+            proportions.push(state.update(opt_x));
+        }
+    }
+    out.into_iter().map(PrimitiveArray::from).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ewm_category_proportions() {
+        let labels = vec![None, Some(0u32), Some(2), Some(2), None, Some(1), Some(0)];
+        let proportions = ewm_category_proportions(labels, 3, 0.3);
+        assert_eq!(proportions.len(), 3);
+
+        for i in 0..7 {
+            let values = proportions
+                .iter()
+                .map(|arr| arr.iter().nth(i).unwrap().copied())
+                .collect::<Vec<_>>();
+            if i == 0 {
+                assert!(values.iter().all(|v| v.is_none()));
+            } else {
+                let total = values.iter().map(|v| v.unwrap()).sum::<f64>();
+                assert!((total - 1.0).abs() < 1e-12);
+            }
+        }
+        // only 0 was seen so far
+        assert_eq!(proportions[0].value(1), 1.0);
+        // 2 was seen most recently and most often
+        assert!(proportions[2].value(4) > proportions[0].value(4));
+    }
+}
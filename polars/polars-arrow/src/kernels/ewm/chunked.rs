@@ -0,0 +1,215 @@
+use std::ops::{AddAssign, Range};
+
+use arrow::array::PrimitiveArray;
+use arrow::types::NativeType;
+use num::Float;
+
+use super::EwmMeanState;
+
+/// Number of values per chunk used by [`ewm_mean_chunked`] unless configured otherwise.
+pub const EWM_DEFAULT_CHUNK_SIZE: usize = 1 << 16;
+
+/// The chunked scan seeds its state every `EWM_SEED_BLOCK` values and chunk sizes are
+/// rounded up to a multiple of it. Because the seed positions do not depend on the
+/// chunk size, neither does the output.
+pub const EWM_SEED_BLOCK: usize = 1 << 10;
+
+/// An exponentially weighted mean split into independent pieces of work, so that it
+/// can be computed in parallel.
+///
+/// 1. [`EwmChunkedScan::new`] counts the non-null values before every seed block.
+/// 2. [`EwmChunkedScan::block_summary`] summarizes each seed block by the
+///    [`EwmMeanState`] over its own values. Blocks are independent.
+/// 3. [`EwmChunkedScan::seed`] chains the summaries with [`EwmMeanState::merge`] into
+///    the state at every block start.
+/// 4. [`EwmChunkedScan::compute_chunk`] computes the output of each chunk from its
+///    seeds. Chunks are independent.
+///
+/// Only steps 2 and 4 do work proportional to the input. The result is bit-identical
+/// for every chunk size, and thus to the single-threaded [`ewm_mean_chunked`]. It is
+/// close to [`ewm_mean`](super::ewm_mean) but not bit-identical: a merged state is
+/// rounded differently than the state reached by updating one value at a time.
+pub struct EwmChunkedScan<'a, T> {
+    xs: &'a [Option<T>],
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    chunk_size: usize,
+    // non-null count before every seed block
+    counts: Vec<usize>,
+    // state before every seed block
+    seeds: Vec<EwmMeanState<T>>,
+}
+
+impl<'a, T> EwmChunkedScan<'a, T>
+where
+    T: Float + NativeType + AddAssign,
+{
+    /// # Panics
+    /// Panics if `chunk_size == 0`.
+    pub fn new(
+        xs: &'a [Option<T>],
+        alpha: T,
+        adjust: bool,
+        min_periods: usize,
+        chunk_size: usize,
+    ) -> Self {
+        assert!(chunk_size > 0);
+        let chunk_size = (chunk_size + EWM_SEED_BLOCK - 1) / EWM_SEED_BLOCK * EWM_SEED_BLOCK;
+
+        let mut non_null_cnt = 0;
+        let counts = xs
+            .chunks(EWM_SEED_BLOCK)
+            .map(|block| {
+                let start = non_null_cnt;
+                non_null_cnt += block.iter().filter(|x| x.is_some()).count();
+                start
+            })
+            .collect();
+
+        Self {
+            xs,
+            alpha,
+            adjust,
+            min_periods,
+            chunk_size,
+            counts,
+            seeds: vec![],
+        }
+    }
+
+    pub fn n_blocks(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// The ranges of the input covered by each chunk.
+    pub fn chunks(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        (0..self.xs.len())
+            .step_by(self.chunk_size)
+            .map(|start| start..std::cmp::min(start + self.chunk_size, self.xs.len()))
+    }
+
+    /// Summarize seed block `b` by the state over its own values.
+    pub fn block_summary(&self, b: usize) -> EwmMeanState<T> {
+        // a block after the first value is merged into a state that has a mean, which
+        // requires normalized weights; the block with the first value is taken as is
+        let adjust = self.counts[b] > 0 || self.adjust;
+        let mut state = EwmMeanState::new(self.alpha, adjust, self.min_periods);
+        for &x in self.block(b) {
+            state.update(x);
+        }
+        state
+    }
+
+    /// Chain the summaries of all seed blocks, in order.
+    pub fn seed(&mut self, summaries: &[EwmMeanState<T>]) {
+        assert_eq!(summaries.len(), self.n_blocks());
+        let mut state = EwmMeanState::new(self.alpha, self.adjust, self.min_periods);
+        self.seeds = summaries
+            .iter()
+            .map(|summary| {
+                let seed = state;
+                state.merge(summary);
+                seed
+            })
+            .collect();
+    }
+
+    fn block(&self, b: usize) -> &'a [Option<T>] {
+        let start = b * EWM_SEED_BLOCK;
+        &self.xs[start..std::cmp::min(start + EWM_SEED_BLOCK, self.xs.len())]
+    }
+
+    /// Compute the output of the input `chunk`, one of [`EwmChunkedScan::chunks`].
+    ///
+    /// # Panics
+    /// Panics if the scan was not seeded.
+    pub fn compute_chunk(&self, chunk: Range<usize>) -> Vec<Option<T>> {
+        assert_eq!(self.seeds.len(), self.n_blocks(), "the scan was not seeded");
+        let mut out = Vec::with_capacity(chunk.len());
+
+        for b in chunk.start / EWM_SEED_BLOCK..(chunk.end + EWM_SEED_BLOCK - 1) / EWM_SEED_BLOCK {
+            let mut state = self.seeds[b];
+            out.extend(self.block(b).iter().map(|&x| state.update(x)));
+        }
+        out
+    }
+}
+
+/// [`ewm_mean`](super::ewm_mean) computed as an [`EwmChunkedScan`] with `chunk_size`
+/// values per chunk, in a single thread.
+///
+/// The output does not depend on `chunk_size`.
+pub fn ewm_mean_chunked<T>(
+    xs: &[Option<T>],
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    chunk_size: usize,
+) -> PrimitiveArray<T>
+where
+    T: Float + NativeType + AddAssign,
+{
+    let mut scan = EwmChunkedScan::new(xs, alpha, adjust, min_periods, chunk_size);
+    let summaries = (0..scan.n_blocks())
+        .map(|b| scan.block_summary(b))
+        .collect::<Vec<_>>();
+    scan.seed(&summaries);
+
+    let out = scan
+        .chunks()
+        .flat_map(|chunk| scan.compute_chunk(chunk))
+        .collect::<Vec<_>>();
+    PrimitiveArray::from(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kernels::ewm::ewm_mean;
+
+    #[test]
+    fn test_ewm_mean_chunked_independent_of_chunk_size() {
+        let xs = (0..5000)
+            .map(|i| match i % 7 {
+                3 => None,
+                _ => Some(((i * 37) % 101) as f64 - 50.0),
+            })
+            .collect::<Vec<_>>();
+        for adjust in [false, true] {
+            let expected = ewm_mean(xs.iter().copied(), 0.01, adjust, 3);
+            let out = ewm_mean_chunked(&xs, 0.01, adjust, 3, EWM_DEFAULT_CHUNK_SIZE);
+            for (a, b) in out.iter().zip(expected.iter()) {
+                match (a, b) {
+                    (Some(a), Some(b)) => assert!((a - b).abs() < 1e-9, "{a} != {b}"),
+                    (a, b) => assert_eq!(a, b),
+                }
+            }
+
+            for chunk_size in [1, 1000, 1024, 2048, 3000, 100_000] {
+                let other = ewm_mean_chunked(&xs, 0.01, adjust, 3, chunk_size);
+                let bits = |arr: &PrimitiveArray<f64>| {
+                    arr.iter()
+                        .map(|x| x.map(|x| x.to_bits()))
+                        .collect::<Vec<_>>()
+                };
+                assert_eq!(bits(&out), bits(&other));
+            }
+        }
+
+        // leading nulls span a whole seed block
+        let mut xs = vec![None; EWM_SEED_BLOCK + 5];
+        xs.extend([Some(1.0), Some(2.0)]);
+        for adjust in [false, true] {
+            let out = ewm_mean_chunked(&xs, 0.5, adjust, 1, 1);
+            assert_eq!(out, ewm_mean(xs.iter().copied(), 0.5, adjust, 1));
+        }
+
+        // with `alpha=1` merging a block takes its state as is
+        let xs = (0..3000)
+            .map(|i| (i % 5 != 2).then_some(i as f64))
+            .collect::<Vec<_>>();
+        let out = ewm_mean_chunked(&xs, 1.0, false, 2, 1);
+        assert_eq!(out, ewm_mean(xs.iter().copied(), 1.0, false, 2));
+    }
+}
@@ -0,0 +1,77 @@
+use crate::trusted_len::{FromIteratorReversed, TrustedLen};
+#[cfg(not(feature = "safe-collect"))]
+use crate::utils::CustomIterTools;
+use crate::utils::FromTrustedLenIterator;
+
+/// How the EWM kernels collect their output.
+///
+/// By default this relies on the length of the iterator being trusted, which skips
+/// bounds checks but needs `unsafe`. With the `safe-collect` feature the output is
+/// collected through a regular `Vec` instead, which is slightly slower. This only
+/// concerns the collection: kernels that use `unsafe` otherwise, such as
+/// [`ewm_of_rolling_std`](super::ewm_of_rolling_std), still do.
+pub(super) trait CollectEwm: Iterator + TrustedLen + Sized {
+    fn collect_ewm<B>(self) -> B
+    where
+        B: FromTrustedLenIterator<Self::Item> + FromIterator<Self::Item>,
+    {
+        #[cfg(not(feature = "safe-collect"))]
+        {
+            self.collect_trusted()
+        }
+        #[cfg(feature = "safe-collect")]
+        {
+            self.collect()
+        }
+    }
+
+    /// Collect the output of an iterator that runs back to front in front to back order.
+    fn collect_ewm_reversed<B>(self) -> B
+    where
+        B: FromIteratorReversed<Self::Item> + FromIterator<Self::Item>,
+    {
+        #[cfg(not(feature = "safe-collect"))]
+        {
+            self.collect_reversed()
+        }
+        #[cfg(feature = "safe-collect")]
+        {
+            let mut out = self.collect::<Vec<_>>();
+            out.reverse();
+            out.into_iter().collect()
+        }
+    }
+}
+
+impl<I: Iterator + TrustedLen> CollectEwm for I {}
+
+#[cfg(test)]
+mod test {
+    use arrow::array::PrimitiveArray;
+
+    use super::*;
+
+    #[test]
+    fn test_collect_ewm() {
+        let xs = vec![Some(1.0f64), None, Some(-3.5), Some(f64::NAN), None];
+        let expected = PrimitiveArray::from(xs.clone());
+
+        let out: PrimitiveArray<f64> = xs.iter().copied().collect_ewm();
+        assert_eq!(out.validity(), expected.validity());
+        assert_eq!(
+            out.values().iter().map(|x| x.to_bits()).collect::<Vec<_>>(),
+            expected
+                .values()
+                .iter()
+                .map(|x| x.to_bits())
+                .collect::<Vec<_>>()
+        );
+
+        let out: PrimitiveArray<f64> = xs.iter().rev().copied().collect_ewm_reversed();
+        assert_eq!(out.validity(), expected.validity());
+        assert_eq!(out.iter().nth(2), Some(Some(&-3.5)));
+
+        let out: Vec<Option<f64>> = xs.iter().copied().collect_ewm();
+        assert_eq!(out.len(), xs.len());
+    }
+}
@@ -0,0 +1,59 @@
+use arrow::array::PrimitiveArray;
+use num::complex::Complex64;
+
+use super::EwmMeanState;
+use crate::trusted_len::TrustedLen;
+
+/// [`ewm_mean`](super::ewm_mean) of complex values, e.g. I/Q samples.
+///
+/// The recurrence is linear, so the real and imaginary parts are smoothed like two
+/// scalar series sharing the same validity. Arrow has no complex type, so
+/// they are returned as two arrays `(re, im)`.
+pub fn ewm_mean_complex<I>(
+    xs: I,
+    alpha: f64,
+    adjust: bool,
+    min_periods: usize,
+) -> (PrimitiveArray<f64>, PrimitiveArray<f64>)
+where
+    I: IntoIterator<Item = Option<Complex64>>,
+    I::IntoIter: TrustedLen,
+{
+    let xs = xs.into_iter();
+    let len = xs.size_hint().0;
+    let mut re = Vec::with_capacity(len);
+    let mut im = Vec::with_capacity(len);
+
+    let mut state_re = EwmMeanState::new(alpha, adjust, min_periods);
+    let mut state_im = EwmMeanState::new(alpha, adjust, min_periods);
+    for opt_x in xs {
+        re.push(state_re.update(opt_x.map(|z| z.re)));
+        im.push(state_im.update(opt_x.map(|z| z.im)));
+    }
+    (PrimitiveArray::from(re), PrimitiveArray::from(im))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kernels::ewm::ewm_mean;
+
+    #[test]
+    fn test_ewm_mean_complex() {
+        let xs = vec![
+            Some(Complex64::new(1.0, -2.0)),
+            None,
+            Some(Complex64::new(3.0, 0.5)),
+            Some(Complex64::new(-4.0, 6.0)),
+            Some(Complex64::new(2.0, 1.0)),
+        ];
+        for (alpha, adjust) in [(0.5, false), (0.5, true), (1.0, true)] {
+            let (re, im) = ewm_mean_complex(xs.clone(), alpha, adjust, 2);
+
+            let expected_re = ewm_mean(xs.iter().map(|z| z.map(|z| z.re)), alpha, adjust, 2);
+            let expected_im = ewm_mean(xs.iter().map(|z| z.map(|z| z.im)), alpha, adjust, 2);
+            assert_eq!(re, expected_re);
+            assert_eq!(im, expected_im);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use std::ops::AddAssign;
+
+use arrow::array::PrimitiveArray;
+use arrow::types::NativeType;
+use num::Float;
+
+use super::collect::CollectEwm;
+use crate::trusted_len::TrustedLen;
+
+/// Exponentially weighted moments of pairs, with the weights of
+/// [`ewm_mean`](super::ewm_mean) under `adjust=true`.
+///
+/// The (co)variances are updated around the running means, as in
+/// [`EwmVarState`](super::EwmVarState), so that a large offset of the values does not
+/// cancel them out.
+struct EwmCorrState<T> {
+    one_sub_alpha: T,
+    wgt_sum: T,
+    mean_x: T,
+    mean_y: T,
+    var_x: T,
+    var_y: T,
+    cov: T,
+}
+
+impl<T: Float + AddAssign> EwmCorrState<T> {
+    fn new(alpha: T) -> Self {
+        Self {
+            one_sub_alpha: T::one() - alpha,
+            wgt_sum: T::zero(),
+            mean_x: T::zero(),
+            mean_y: T::zero(),
+            var_x: T::zero(),
+            var_y: T::zero(),
+            cov: T::zero(),
+        }
+    }
+
+    fn update(&mut self, x: T, y: T) {
+        self.wgt_sum = self.wgt_sum * self.one_sub_alpha + T::one();
+        // the weight of the new pair, `1` for the first one
+        let wgt = self.wgt_sum.recip();
+        let (dx, dy) = (x - self.mean_x, y - self.mean_y);
+
+        self.mean_x += wgt * dx;
+        self.mean_y += wgt * dy;
+        self.var_x = (T::one() - wgt) * (self.var_x + wgt * dx * dx);
+        self.var_y = (T::one() - wgt) * (self.var_y + wgt * dy * dy);
+        self.cov = (T::one() - wgt) * (self.cov + wgt * dx * dy);
+    }
+
+    /// The correlation of the pairs so far, `None` if it is undefined.
+    fn corr(&self) -> Option<T> {
+        if self.wgt_sum.is_zero() {
+            return None;
+        }
+        let denom = (self.var_x * self.var_y).sqrt();
+        (denom > T::zero()).then(|| self.cov / denom)
+    }
+}
+
+/// Exponentially weighted correlation as of the last pair.
+fn ewm_final_corr<I, T>(pairs: I, alpha: T) -> Option<T>
+where
+    I: Iterator<Item = (T, T)>,
+    T: Float + AddAssign,
+{
+    let mut state = EwmCorrState::new(alpha);
+    for (x, y) in pairs {
+        state.update(x, y);
+    }
+    state.corr()
+}
+
+/// Exponentially weighted lag-1 autocorrelation, the correlation of each value with
+/// its predecessor, as of every position.
+///
+/// A pair only counts if both values are non-null. The output is null before the
+/// first pair and while the correlation is undefined, e.g. for a constant input.
+/// Values near one indicate a smooth input, e.g. an over-smoothed signal.
+pub fn ewm_autocorr1<I, T>(xs: I, alpha: T) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut state = EwmCorrState::new(alpha);
+    let mut opt_prev = None;
+
+    xs.into_iter()
+        .map(|opt_x| {
+            if let (Some(prev), Some(x)) = (opt_prev, opt_x) {
+                state.update(prev, x);
+            }
+            opt_prev = opt_x;
+            state.corr()
+        })
+        .collect_ewm()
+}
+
+/// Running slope of an exponentially weighted least-squares fit of the values against
+/// their position, a smoothed estimate of the trend per step.
+///
+/// Observations are weighted like the [`ewm_mean`](super::ewm_mean) under
+/// `adjust=true`. Positions are measured relative to the current one, which keeps the
+/// weighted sums of `t` and `t^2` small on long inputs. Nulls only advance the
+/// position. The slope is null until two observations at different positions were
+/// seen.
+pub fn ewm_slope<I, T>(xs: I, alpha: T) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let one_sub_alpha = T::one() - alpha;
+    let two = T::from(2).unwrap();
+    // weighted sums of 1, t, t^2, x and t * x, with t <= 0 the offset to the
+    // current position
+    let [mut sw, mut st, mut stt, mut sx, mut stx] = [T::zero(); 5];
+
+    xs.into_iter()
+        .enumerate()
+        .map(|(i, opt_x)| {
+            if i > 0 {
+                // every t decreases by one
+                stt = stt - two * st + sw;
+                stx = stx - sx;
+                st = st - sw;
+            }
+            if let Some(x) = opt_x {
+                for s in [&mut sw, &mut st, &mut stt, &mut sx, &mut stx] {
+                    *s = *s * one_sub_alpha;
+                }
+                // t = 0 for the new observation
+                sw += T::one();
+                sx += x;
+            }
+            let denom = sw * stt - st * st;
+            (denom > T::zero()).then(|| (sw * stx - st * sx) / denom)
+        })
+        .collect_ewm()
+}
+
+/// The lag in `[-max_lag, max_lag]` at which `b` is most correlated with `a`.
+///
+/// For a lag `l`, `a[i]` is paired with `b[i + l]`, so a positive result means `b`
+/// lags behind `a`. Pairs with a null are skipped and each lag is scored by the
+/// exponentially weighted correlation as of its last pair. Returns `0` if the
+/// correlation is undefined at every lag, e.g. for constant inputs.
+///
+/// # Panics
+/// Panics if `a` and `b` differ in length.
+pub fn best_lag_ewm<T>(a: &[Option<T>], b: &[Option<T>], alpha: T, max_lag: usize) -> i32
+where
+    T: Float + NativeType + AddAssign,
+{
+    assert_eq!(a.len(), b.len(), "a and b must have equal length");
+    let max_lag = max_lag as i64;
+
+    let mut best: Option<(i64, T)> = None;
+    for lag in -max_lag..=max_lag {
+        let (a, b) = if lag >= 0 {
+            (a, b.get(lag as usize..).unwrap_or_default())
+        } else {
+            (a.get(-lag as usize..).unwrap_or_default(), b)
+        };
+        let pairs = a.iter().zip(b).filter_map(|(x, y)| Some(((*x)?, (*y)?)));
+
+        if let Some(corr) = ewm_final_corr(pairs, alpha) {
+            match best {
+                Some((_, best_corr)) if corr <= best_corr => {}
+                _ => best = Some((lag, corr)),
+            }
+        }
+    }
+    best.map_or(0, |(lag, _)| lag as i32)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kernels::ewm::golden::uniform_noise;
+
+    #[test]
+    fn test_best_lag_ewm() {
+        let a = uniform_noise(0x9e37_79b9_7f4a_7c15)
+            .take(300)
+            .map(Some)
+            .collect::<Vec<_>>();
+        // b[t] = a[t - 3]
+        let mut b = vec![None; 3];
+        b.extend_from_slice(&a[..a.len() - 3]);
+
+        assert_eq!(best_lag_ewm(&a, &b, 0.05, 5), 3);
+        assert_eq!(best_lag_ewm(&b, &a, 0.05, 5), -3);
+
+        let constant = vec![Some(1.0); 10];
+        assert_eq!(best_lag_ewm(&constant, &constant, 0.05, 2), 0);
+    }
+
+    #[test]
+    fn test_ewm_autocorr1() {
+        let noise = uniform_noise(0x2545_f491_4f6c_dd1d)
+            .take(2000)
+            .map(|x| Some(x - 0.5))
+            .collect::<Vec<_>>();
+
+        let out = ewm_autocorr1(noise.clone(), 0.01);
+        assert_eq!(out.iter().next().unwrap(), None);
+        assert!(out.value(1999).abs() < 0.15);
+
+        let smoothed = crate::kernels::ewm::ewm_mean(noise, 0.05, true, 1);
+        let out = ewm_autocorr1(smoothed.iter().map(|v| v.copied()), 0.01);
+        assert!(out.value(1999) > 0.9);
+
+        let trend = (0..100).map(|i| Some(i as f64)).collect::<Vec<_>>();
+        let out = ewm_autocorr1(trend, 0.1);
+        assert!(out.value(99) > 0.999);
+
+        let out = ewm_autocorr1(vec![Some(1.0f64), None, Some(2.0), Some(2.0)], 0.5);
+        assert!(out.iter().all(|v| v.is_none()));
+    }
+
+    #[test]
+    fn test_ewm_autocorr1_large_offset() {
+        // the same smoothed noise, once around zero and once around 1e6
+        let noise = uniform_noise(0x2545_f491_4f6c_dd1d)
+            .take(2000)
+            .map(|x| Some(1e-3 * (x - 0.5)))
+            .collect::<Vec<_>>();
+        let smoothed = crate::kernels::ewm::ewm_mean(noise, 0.05, true, 1);
+        let centred = ewm_autocorr1(smoothed.iter().map(|v| v.copied()), 0.01);
+        let shifted = ewm_autocorr1(smoothed.iter().map(|v| v.map(|v| v + 1e6)), 0.01);
+
+        assert!(centred.value(1999) > 0.9);
+        for (a, b) in centred.iter().zip(shifted.iter()).skip(10) {
+            assert!((a.unwrap() - b.unwrap()).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_ewm_slope() {
+        let xs = uniform_noise(0x9e37_79b9_7f4a_7c15)
+            .take(3000)
+            .enumerate()
+            .map(|(i, noise)| (i % 10 != 3).then_some(0.25 * i as f64 + noise - 0.5))
+            .collect::<Vec<_>>();
+
+        let slope = ewm_slope(xs, 0.02);
+        assert_eq!(slope.iter().next().unwrap(), None);
+        assert!((slope.value(2999) - 0.25).abs() < 0.01);
+
+        // exact on a clean line, including across a null
+        let slope = ewm_slope(vec![Some(1.0f64), None, Some(5.0), Some(7.0)], 0.5);
+        assert_eq!(
+            slope,
+            PrimitiveArray::from([None, None, Some(2.0), Some(2.0)])
+        );
+    }
+}
@@ -0,0 +1,620 @@
+use std::ops::AddAssign;
+
+use arrow::array::{BooleanArray, PrimitiveArray};
+use arrow::types::NativeType;
+use num::Float;
+
+use super::collect::CollectEwm;
+use super::{EwmMeanState, EwmVarState};
+use crate::trusted_len::TrustedLen;
+
+/// Compute the [`ewm_mean`](super::ewm_mean) together with the share `wgt / wgt_sum` the
+/// newest observation has in the updated mean.
+///
+/// The share indicates how reactive the smoother is at each position. It is null
+/// where the input is null or fewer than `min_periods` observations were seen.
+pub fn ewm_mean_with_newest_share<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> (PrimitiveArray<T>, PrimitiveArray<T>)
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    let mut shares = Vec::with_capacity(xs.size_hint().0);
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+
+    let means = xs
+        .map(|opt_x| {
+            let out = state.update(opt_x);
+            let share = match (opt_x, out) {
+                // a zero `wgt_sum` gives the observation no weight, see `EwmMeanState`
+                (Some(_), Some(_)) if state.wgt_sum().is_zero() => Some(T::zero()),
+                (Some(_), Some(_)) => Some(alpha / state.wgt_sum()),
+                _ => None,
+            };
+            shares.push(share);
+            out
+        })
+        .collect_ewm();
+    (means, PrimitiveArray::from(shares))
+}
+
+/// Compute the [`ewm_mean`](super::ewm_mean) together with a mask that flags stale values.
+///
+/// A value is stale when the mean has not been updated by an observation for more
+/// than `max_stale` steps, e.g. because of a long run of nulls. The mask is null where
+/// the mean is null.
+pub fn ewm_mean_with_staleness<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    max_stale: usize,
+) -> (PrimitiveArray<T>, BooleanArray)
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    let mut stale = Vec::with_capacity(xs.size_hint().0);
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    let mut steps_since_update = 0usize;
+
+    let means = xs
+        .map(|opt_x| {
+            steps_since_update = match opt_x {
+                Some(_) => 0,
+                None => steps_since_update + 1,
+            };
+            let out = state.update(opt_x);
+            stale.push(out.map(|_| steps_since_update > max_stale));
+            out
+        })
+        .collect_ewm();
+    (means, BooleanArray::from(stale))
+}
+
+/// Per-position diagnostics of the [`ewm_mean`](super::ewm_mean) recurrence, see
+/// [`ewm_mean_diagnostics`]. All arrays have the length of the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EwmDiagnostics<T: NativeType> {
+    /// The exponentially weighted mean.
+    pub mean: PrimitiveArray<T>,
+    /// The sum of the weights, null before the first observation.
+    pub wgt_sum: PrimitiveArray<T>,
+    /// The effective number of observations `wgt_sum^2 / wgt_sum_sqr`, null before
+    /// the first observation.
+    pub effective_n: PrimitiveArray<T>,
+    /// Whether at least `min_periods` observations were seen.
+    pub min_periods_reached: BooleanArray,
+}
+
+/// Compute the [`ewm_mean`](super::ewm_mean) together with its weight sum, effective
+/// number of observations and `min_periods` mask in a single pass.
+pub fn ewm_mean_diagnostics<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> EwmDiagnostics<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    let len = xs.size_hint().0;
+    let mut wgt_sums = Vec::with_capacity(len);
+    let mut effective_n = Vec::with_capacity(len);
+    let mut min_periods_reached = Vec::with_capacity(len);
+
+    let mut state = EwmVarState::new(alpha, adjust, true, min_periods);
+    let mut non_null_cnt = 0usize;
+
+    let mean = xs
+        .map(|opt_x| {
+            non_null_cnt += opt_x.is_some() as usize;
+            let out = state.update(opt_x);
+
+            let opt_effective_n = state.effective_n();
+            wgt_sums.push(opt_effective_n.map(|_| state.wgt_sum()));
+            effective_n.push(opt_effective_n);
+            min_periods_reached.push(non_null_cnt >= min_periods);
+            out
+        })
+        .collect_ewm();
+
+    EwmDiagnostics {
+        mean,
+        wgt_sum: PrimitiveArray::from(wgt_sums),
+        effective_n: PrimitiveArray::from(effective_n),
+        min_periods_reached: BooleanArray::from_slice(min_periods_reached),
+    }
+}
+
+/// What happened to the input at a position of [`ewm_mean_provenance`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Provenance {
+    /// The observation updated the mean.
+    Value,
+    /// The input was null.
+    SkippedNull,
+    /// The input was `NaN` and treated as null.
+    SkippedNan,
+}
+
+/// Compute the [`ewm_mean`](super::ewm_mean) treating `NaN` as null, together with the
+/// reason each input was used or skipped.
+///
+/// This helps telling apart missing data from `NaN`s produced upstream.
+pub fn ewm_mean_provenance<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> (PrimitiveArray<T>, Vec<Provenance>)
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    let mut provenance = Vec::with_capacity(xs.size_hint().0);
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+
+    let means = xs
+        .map(|opt_x| {
+            let (opt_x, kind) = match opt_x {
+                None => (None, Provenance::SkippedNull),
+                Some(x) if x.is_nan() => (None, Provenance::SkippedNan),
+                Some(x) => (Some(x), Provenance::Value),
+            };
+            provenance.push(kind);
+            state.update(opt_x)
+        })
+        .collect_ewm();
+    (means, provenance)
+}
+
+/// Estimate the half-life of an exponentially weighted mean that produced `xs`.
+///
+/// Assuming `xs` is the unadjusted EWM of a step, the consecutive differences decay
+/// geometrically: `x[t] - x[t-1] = c * (1 - alpha)^t`. The slope `b` of a least-squares
+/// fit of `ln|x[t] - x[t-1]|` against `t` thus estimates `ln(1 - alpha)`, and the
+/// half-life is `-ln(2) / b`. Zero differences and differences next to a null are
+/// skipped. Returns `None` if fewer than two differences remain or they do not decay.
+pub fn estimate_ewm_halflife<I, T>(xs: I) -> Option<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    T: Float,
+{
+    let mut opt_prev: Option<(usize, T)> = None;
+    let (mut n, mut st, mut sy, mut stt, mut sty) =
+        (T::zero(), T::zero(), T::zero(), T::zero(), T::zero());
+
+    for (t, opt_x) in xs.into_iter().enumerate() {
+        if let (Some((prev_t, prev)), Some(x)) = (opt_prev, opt_x) {
+            let diff = (x - prev).abs();
+            if prev_t + 1 == t && diff > T::zero() && diff.is_finite() {
+                let t = T::from(t).unwrap();
+                let y = diff.ln();
+                n = n + T::one();
+                st = st + t;
+                sy = sy + y;
+                stt = stt + t * t;
+                sty = sty + t * y;
+            }
+        }
+        opt_prev = opt_x.map(|x| (t, x));
+    }
+
+    let denom = n * stt - st * st;
+    if n < T::from(2).unwrap() || denom.is_zero() {
+        return None;
+    }
+    let slope = (n * sty - st * sy) / denom;
+    (slope < T::zero()).then(|| -T::from(2).unwrap().ln() / slope)
+}
+
+/// Response of the unadjusted [`ewm_mean`](super::ewm_mean) to a unit step.
+///
+/// Element `k` is the output `k` steps after the input jumps from `0` to `1`, i.e.
+/// `1 - (1 - alpha)^k`; the first element is the level before the step. This shows
+/// how many steps a given `alpha` needs to reach e.g. 50% or 90% of a new level.
+pub fn ewm_step_response(alpha: f64, n: usize) -> Vec<f64> {
+    let mut state = EwmMeanState::new(alpha, false, 1);
+    (0..=n)
+        .map(|k| {
+            let x = if k == 0 { 0.0 } else { 1.0 };
+            state.update(Some(x)).unwrap()
+        })
+        .collect()
+}
+
+/// The `alpha` at which the unadjusted [`ewm_mean`](super::ewm_mean) of white noise
+/// has `target_var_ratio` times the variance of its input.
+///
+/// Solves `alpha / (2 - alpha) = target_var_ratio`, e.g. `0.1` ("reduce the noise
+/// variance to 10%") gives `alpha = 2 / 11`.
+///
+/// # Panics
+/// Panics if `target_var_ratio` is not in `(0, 1]`.
+pub fn ewm_alpha_for_noise_reduction(target_var_ratio: f64) -> f64 {
+    assert!(target_var_ratio > 0.0 && target_var_ratio <= 1.0);
+    2.0 * target_var_ratio / (1.0 + target_var_ratio)
+}
+
+/// The effective number of lags contributing to [`ewm_mean`](super::ewm_mean) at each
+/// of `len` positions, assuming no nulls.
+///
+/// This is the sum of the weights relative to the weight of the newest observation:
+/// `(1 - (1 - alpha)^t) / alpha` after `t` observations with `adjust`, and `1 / alpha`
+/// without. With `adjust` it grows towards `1 / alpha` during the warmup.
+pub fn ewm_memory<T>(alpha: T, len: usize, adjust: bool) -> PrimitiveArray<T>
+where
+    T: Float + NativeType,
+{
+    let mut wgt_sum = if adjust { T::zero() } else { T::one() };
+    let out = (0..len)
+        .map(|_| {
+            wgt_sum = (T::one() - alpha) * wgt_sum + alpha;
+            wgt_sum / alpha
+        })
+        .collect::<Vec<_>>();
+    PrimitiveArray::from_vec(out)
+}
+
+/// The largest `alpha` for which the observation `lag` steps before the newest one
+/// keeps at least `min_weight` of the newest observation's weight.
+///
+/// The relative weight is `(1 - alpha)^lag`, so this is `1 - min_weight^(1 / lag)`,
+/// e.g. `lag=10, min_weight=0.5` gives the `alpha` with a half-life of 10 steps.
+///
+/// # Panics
+/// Panics if `min_weight` is not in `(0, 1]`.
+pub fn max_alpha_for_lag_weight(lag: usize, min_weight: f64) -> f64 {
+    assert!(min_weight > 0.0 && min_weight <= 1.0);
+    if lag == 0 {
+        return 1.0;
+    }
+    1.0 - min_weight.powf(1.0 / lag as f64)
+}
+
+/// The number of observations after which the weight of the first one in the
+/// unadjusted [`ewm_mean`](super::ewm_mean), `(1 - alpha)^n`, drops below `tol`.
+///
+/// This is `ceil(ln(tol) / ln(1 - alpha))` up to rounding, and a reasonable choice of
+/// `min_periods` when the start of the series should not dominate the output. The
+/// logarithm is taken with `ln_1p`, so an `alpha` too small to change `1 - alpha` still
+/// gives the right count. Counts beyond `usize::MAX` saturate.
+///
+/// # Panics
+/// Panics if `alpha` is not in `(0, 1]` or `tol` is not positive.
+pub fn ewm_convergence_steps(alpha: f64, tol: f64) -> usize {
+    assert!(alpha > 0.0 && alpha <= 1.0);
+    assert!(tol > 0.0);
+    if alpha == 1.0 {
+        // the first observation has no weight from the second one on
+        return usize::from(tol <= 1.0);
+    }
+    let ln_decay = (-alpha).ln_1p();
+    let weight = |n: usize| (n as f64 * ln_decay).exp();
+
+    let n = (tol.ln() / ln_decay).ceil().max(0.0);
+    if n >= usize::MAX as f64 {
+        return usize::MAX;
+    }
+    // the closed form may be off by one due to rounding
+    let mut n = n as usize;
+    if weight(n) >= tol {
+        n += 1;
+    } else if n > 0 && weight(n - 1) < tol {
+        n -= 1;
+    }
+    n
+}
+
+/// Compare EWM output against a reference, e.g. exported from another library, and
+/// return the indices where they differ.
+///
+/// Two finite values match if `|computed - reference| <= atol + rtol * |reference|`.
+/// Infinities only match if they are equal, `NaN` matches `NaN` and null matches null.
+///
+/// # Panics
+/// Panics if `computed` and `reference` differ in length.
+pub fn ewm_assert_close<T>(
+    computed: &PrimitiveArray<T>,
+    reference: &PrimitiveArray<T>,
+    rtol: T,
+    atol: T,
+) -> Result<(), Vec<usize>>
+where
+    T: Float + NativeType,
+{
+    assert_eq!(
+        computed.len(),
+        reference.len(),
+        "computed and reference must have the same length"
+    );
+    let mismatches = computed
+        .iter()
+        .zip(reference.iter())
+        .enumerate()
+        .filter_map(|(i, pair)| {
+            let close = match pair {
+                (Some(&a), Some(&b)) if a.is_nan() || b.is_nan() => a.is_nan() && b.is_nan(),
+                (Some(&a), Some(&b)) if a.is_infinite() || b.is_infinite() => a == b,
+                (Some(&a), Some(&b)) => (a - b).abs() <= atol + rtol * b.abs(),
+                (a, b) => a.is_none() && b.is_none(),
+            };
+            (!close).then_some(i)
+        })
+        .collect::<Vec<_>>();
+    match mismatches.is_empty() {
+        true => Ok(()),
+        false => Err(mismatches),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kernels::ewm::ewm_mean;
+
+    #[test]
+    fn test_ewm_mean_with_newest_share() {
+        let xs = vec![
+            Some(1.0f64),
+            Some(5.0),
+            None,
+            Some(7.0),
+            Some(2.0),
+            Some(3.0),
+        ];
+
+        let (means, shares) = ewm_mean_with_newest_share(xs.clone(), 0.25, false, 1);
+        assert_eq!(means, ewm_mean(xs.clone(), 0.25, false, 1));
+        assert_eq!(
+            shares,
+            PrimitiveArray::from([
+                Some(0.25),
+                Some(0.25),
+                None,
+                Some(0.25),
+                Some(0.25),
+                Some(0.25)
+            ])
+        );
+
+        let (means, shares) = ewm_mean_with_newest_share(xs.clone(), 0.25, true, 1);
+        assert_eq!(means, ewm_mean(xs.clone(), 0.25, true, 1));
+        // the first observation has the full weight, then the share decays towards alpha
+        let shares = shares.iter().flatten().copied().collect::<Vec<_>>();
+        assert_eq!(shares[0], 1.0);
+        assert!(shares.windows(2).all(|w| w[0] > w[1] && w[1] > 0.25));
+
+        // with `alpha=1` the output at a null is null, as in `ewm_mean`
+        for adjust in [false, true] {
+            let (means, shares) = ewm_mean_with_newest_share(xs.clone(), 1.0, adjust, 1);
+            assert_eq!(means, ewm_mean(xs.clone(), 1.0, adjust, 1));
+            assert!(shares.iter().flatten().all(|&share| share == 1.0));
+        }
+
+        let xs = vec![Some(1.0f64); 200];
+        let (_, shares) = ewm_mean_with_newest_share(xs, 0.25, true, 1);
+        assert!((shares.value(199) - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_ewm_mean_with_staleness() {
+        let mut xs = vec![None, Some(1.0f64), Some(2.0)];
+        xs.extend([None; 5]);
+        xs.push(Some(3.0));
+
+        let (means, stale) = ewm_mean_with_staleness(xs.clone(), 0.5, true, 1, 2);
+        assert_eq!(means, ewm_mean(xs.clone(), 0.5, true, 1));
+        assert_eq!(
+            stale,
+            BooleanArray::from([
+                None,
+                Some(false),
+                Some(false),
+                Some(false),
+                Some(false),
+                Some(true),
+                Some(true),
+                Some(true),
+                Some(false),
+            ])
+        );
+
+        // with `alpha=1` the output at a null is null, as in `ewm_mean`
+        let (means, stale) = ewm_mean_with_staleness(xs.clone(), 1.0, true, 1, 2);
+        assert_eq!(means, ewm_mean(xs, 1.0, true, 1));
+        assert_eq!(stale.iter().filter(|v| v.is_none()).count(), 6);
+    }
+
+    #[test]
+    fn test_ewm_mean_diagnostics() {
+        let xs = vec![None, Some(1.0f64), None, Some(3.0)];
+        let diagnostics = ewm_mean_diagnostics(xs.clone(), 0.5, true, 2);
+
+        assert_eq!(diagnostics.mean, ewm_mean(xs.clone(), 0.5, true, 2));
+        assert!(diagnostics.mean.iter().take(3).all(|v| v.is_none()));
+        assert_eq!(
+            diagnostics.wgt_sum,
+            PrimitiveArray::from([None, Some(0.5), Some(0.5), Some(0.75)])
+        );
+        assert_eq!(
+            diagnostics.effective_n,
+            PrimitiveArray::from([None, Some(1.0), Some(1.0), Some(1.8)])
+        );
+        assert_eq!(
+            diagnostics.min_periods_reached,
+            BooleanArray::from_slice([false, false, false, true])
+        );
+
+        // only the newest observation has weight
+        for adjust in [true, false] {
+            let diagnostics = ewm_mean_diagnostics(xs.clone(), 1.0, adjust, 0);
+            assert_eq!(diagnostics.mean, ewm_mean(xs.clone(), 1.0, adjust, 0));
+            assert_eq!(
+                diagnostics.effective_n,
+                PrimitiveArray::from([None, Some(1.0), Some(1.0), Some(1.0)])
+            );
+        }
+    }
+
+    #[test]
+    fn test_ewm_step_response() {
+        let alpha = 0.3;
+        let response = ewm_step_response(alpha, 20);
+        assert_eq!(response.len(), 21);
+        assert_eq!(response[0], 0.0);
+        for (n, r) in response.iter().enumerate() {
+            assert!((r - (1.0 - (1.0 - alpha).powi(n as i32))).abs() < 1e-12);
+        }
+        // half the step is reached after two steps
+        assert_eq!(response.iter().position(|&r| r >= 0.5), Some(2));
+    }
+
+    #[test]
+    fn test_ewm_mean_provenance() {
+        let xs = vec![
+            Some(1.0f64),
+            None,
+            Some(f64::NAN),
+            Some(3.0),
+            Some(f64::NAN),
+            None,
+        ];
+        let (result, provenance) = ewm_mean_provenance(xs, 0.5, false, 1);
+
+        let expected = PrimitiveArray::from([
+            Some(1.0),
+            Some(1.0),
+            Some(1.0),
+            Some(2.0),
+            Some(2.0),
+            Some(2.0),
+        ]);
+        assert_eq!(result, expected);
+        use Provenance::*;
+        assert_eq!(
+            provenance,
+            [
+                Value,
+                SkippedNull,
+                SkippedNan,
+                Value,
+                SkippedNan,
+                SkippedNull
+            ]
+        );
+    }
+
+    #[test]
+    fn test_estimate_ewm_halflife() {
+        let half_life = 5.0f64;
+        let alpha = 1.0 - 0.5f64.powf(1.0 / half_life);
+        let xs = (0..40)
+            .map(|t| Some(if t < 3 { 2.0 } else { 10.0 }))
+            .collect::<Vec<_>>();
+        let smoothed = ewm_mean(xs, alpha, false, 1);
+        let mut smoothed = smoothed.iter().map(|v| v.copied()).collect::<Vec<_>>();
+        smoothed[20] = None;
+
+        let estimate = estimate_ewm_halflife(smoothed).unwrap();
+        assert!((estimate - half_life).abs() < 1e-6);
+
+        assert!(estimate_ewm_halflife(vec![Some(1.0f64); 10]).is_none());
+    }
+
+    #[test]
+    fn test_ewm_alpha_for_noise_reduction() {
+        let alpha = ewm_alpha_for_noise_reduction(0.1);
+        assert!((alpha - 2.0 / 11.0).abs() < 1e-15);
+        assert!((alpha / (2.0 - alpha) - 0.1).abs() < 1e-15);
+        assert_eq!(ewm_alpha_for_noise_reduction(1.0), 1.0);
+    }
+
+    #[test]
+    fn test_ewm_memory() {
+        let memory = ewm_memory(0.25f64, 200, true);
+        assert_eq!(memory.value(0), 1.0);
+        assert!((memory.value(1) - 1.75).abs() < 1e-12);
+        assert!(memory.values().windows(2).all(|w| w[0] <= w[1]));
+        assert!((memory.value(199) - 4.0).abs() < 1e-12);
+
+        let memory = ewm_memory(0.25f64, 3, false);
+        assert_eq!(memory.values().as_slice(), &[4.0, 4.0, 4.0]);
+    }
+
+    #[test]
+    fn test_max_alpha_for_lag_weight() {
+        assert_eq!(max_alpha_for_lag_weight(1, 0.5), 0.5);
+        assert_eq!(max_alpha_for_lag_weight(0, 0.1), 1.0);
+
+        let alpha = max_alpha_for_lag_weight(10, 0.5);
+        assert!((alpha - 0.066967).abs() < 1e-6);
+        assert!(((1.0 - alpha).powi(10) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_ewm_convergence_steps() {
+        for (alpha, tol) in [(0.5, 1e-3), (0.1, 0.01), (0.02, 1e-6), (0.3, 0.2)] {
+            let n = ewm_convergence_steps(alpha, tol);
+            assert_eq!(n, (tol.ln() / (1.0f64 - alpha).ln()).ceil() as usize);
+            assert!((1.0 - alpha).powi(n as i32) < tol);
+            assert!((1.0 - alpha).powi(n as i32 - 1) >= tol);
+        }
+        // exactly on the boundary the weight has to drop strictly below `tol`
+        assert_eq!(ewm_convergence_steps(0.5, 0.25), 3);
+        assert_eq!(ewm_convergence_steps(1.0, 0.5), 1);
+        assert_eq!(ewm_convergence_steps(0.5, 2.0), 0);
+
+        // `1 - alpha` rounds to one
+        let n = ewm_convergence_steps(1e-17, 0.5);
+        assert!((n as f64 * 1e-17 - 2.0f64.ln()).abs() < 1e-12);
+        assert_eq!(ewm_convergence_steps(1e-300, 0.5), usize::MAX);
+    }
+
+    #[test]
+    fn test_ewm_assert_close() {
+        let xs = vec![
+            Some(1.0f64),
+            None,
+            Some(4.0),
+            Some(f64::NAN),
+            Some(2.0),
+            Some(8.0),
+        ];
+        let computed = ewm_mean(xs.clone(), 0.5, true, 1);
+        assert_eq!(ewm_assert_close(&computed, &computed, 0.0, 0.0), Ok(()));
+
+        let mut reference = computed.iter().map(|v| v.copied()).collect::<Vec<_>>();
+        reference[0] = reference[0].map(|v| v * (1.0 + 1e-9));
+        reference[2] = reference[2].map(|v| v + 1e-3);
+        reference[4] = None;
+        let reference = PrimitiveArray::from(reference);
+        assert_eq!(
+            ewm_assert_close(&computed, &reference, 1e-6, 1e-12),
+            Err(vec![2, 4])
+        );
+        assert_eq!(
+            ewm_assert_close(&computed, &reference, 0.0, 0.0),
+            Err(vec![0, 2, 4])
+        );
+
+        let inf = PrimitiveArray::from([Some(f64::INFINITY), Some(f64::NEG_INFINITY)]);
+        let flipped = PrimitiveArray::from([Some(f64::NEG_INFINITY), Some(f64::NEG_INFINITY)]);
+        assert_eq!(ewm_assert_close(&inf, &inf, 0.0, 0.0), Ok(()));
+        assert_eq!(ewm_assert_close(&inf, &flipped, 1.0, 1.0), Err(vec![0]));
+    }
+}
@@ -0,0 +1,165 @@
+use arrow::array::PrimitiveArray;
+
+use super::collect::CollectEwm;
+use crate::trusted_len::TrustedLen;
+
+/// An unevaluated sum `hi + lo` of two `f64`s, with about 106 bits of precision.
+#[derive(Debug, Copy, Clone)]
+struct DoubleDouble {
+    hi: f64,
+    lo: f64,
+}
+
+impl DoubleDouble {
+    fn new(x: f64) -> Self {
+        Self { hi: x, lo: 0.0 }
+    }
+
+    // `a + b` as a sum of two `f64`s, exactly
+    fn two_sum(a: f64, b: f64) -> Self {
+        let hi = a + b;
+        let b_virtual = hi - a;
+        let lo = (a - (hi - b_virtual)) + (b - b_virtual);
+        Self { hi, lo }
+    }
+
+    // like `two_sum`, requires `|a| >= |b|`
+    fn quick_two_sum(a: f64, b: f64) -> Self {
+        let hi = a + b;
+        Self {
+            hi,
+            lo: b - (hi - a),
+        }
+    }
+
+    fn add(self, other: Self) -> Self {
+        let s = Self::two_sum(self.hi, other.hi);
+        let t = Self::two_sum(self.lo, other.lo);
+        let s = Self::quick_two_sum(s.hi, s.lo + t.hi);
+        Self::quick_two_sum(s.hi, s.lo + t.lo)
+    }
+
+    fn sub(self, other: Self) -> Self {
+        self.add(Self {
+            hi: -other.hi,
+            lo: -other.lo,
+        })
+    }
+
+    fn mul(self, other: Self) -> Self {
+        let hi = self.hi * other.hi;
+        let lo = self.hi.mul_add(other.hi, -hi) + (self.hi * other.lo + self.lo * other.hi);
+        Self::quick_two_sum(hi, lo)
+    }
+
+    fn div(self, other: Self) -> Self {
+        let q1 = self.hi / other.hi;
+        let r = self.sub(other.mul(Self::new(q1)));
+        let q2 = r.hi / other.hi;
+        let r = r.sub(other.mul(Self::new(q2)));
+        let q3 = r.hi / other.hi;
+        Self::quick_two_sum(q1, q2).add(Self::new(q3))
+    }
+}
+
+/// [`ewm_mean`](super::ewm_mean) with the recurrence evaluated in double-double
+/// arithmetic, for reference-quality results.
+///
+/// The mean and the weights are carried with about twice the precision of an `f64`
+/// and only the output is rounded, so every output is within about half an ulp of
+/// the exact exponentially weighted mean of the inputs. This is several times slower
+/// than [`ewm_mean`](super::ewm_mean) and meant for validating it.
+pub fn ewm_mean_extended<I>(
+    xs: I,
+    alpha: f64,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<f64>
+where
+    I: IntoIterator<Item = Option<f64>>,
+    I::IntoIter: TrustedLen,
+{
+    let alpha_dd = DoubleDouble::new(alpha);
+    let one_sub_alpha = DoubleDouble::new(1.0).sub(alpha_dd);
+
+    let mut opt_mean: Option<DoubleDouble> = None;
+    let mut non_null_cnt = 0usize;
+    let mut wgt_sum = DoubleDouble::new(if adjust { 0.0 } else { 1.0 });
+
+    xs.into_iter()
+        .map(|opt_x| {
+            if let Some(x) = opt_x {
+                non_null_cnt += 1;
+                let x = DoubleDouble::new(x);
+
+                let prev_mean = opt_mean.unwrap_or(x);
+                wgt_sum = one_sub_alpha.mul(wgt_sum).add(alpha_dd);
+                let step = x.sub(prev_mean).mul(alpha_dd).div(wgt_sum);
+                opt_mean = Some(prev_mean.add(step));
+            }
+            match non_null_cnt < min_periods {
+                true => None,
+                false => opt_mean.map(|mean| mean.hi + mean.lo),
+            }
+        })
+        .collect_ewm()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kernels::ewm::ewm_mean;
+
+    #[test]
+    fn test_ewm_mean_extended() {
+        let xs = (0..30)
+            .map(|k| Some(1e8 + k as f64 * 0.37))
+            .collect::<Vec<_>>();
+        // the recurrence for alpha = 0.1 and adjust = true evaluated with Python's
+        // `decimal` at 80 digits, rounded to the nearest f64
+        let reference = [
+            100000000.0,
+            100000000.19473684,
+            100000000.39594096,
+            100000000.60357662,
+            100000000.8175942,
+            100000001.03793055,
+            100000001.26450951,
+            100000001.49724247,
+            100000001.73602901,
+            100000001.98075774,
+            100000002.23130699,
+            100000002.48754565,
+            100000002.74933423,
+            100000003.01652563,
+            100000003.28896615,
+            100000003.56649646,
+            100000003.84895253,
+            100000004.13616656,
+            100000004.42796795,
+            100000004.72418413,
+            100000005.02464145,
+            100000005.329166,
+            100000005.63758431,
+            100000005.94972417,
+            100000006.26541515,
+            100000006.58448927,
+            100000006.90678155,
+            100000007.23213045,
+            100000007.56037831,
+            100000007.89137167,
+        ];
+
+        let extended = ewm_mean_extended(xs.clone(), 0.1, true, 1);
+        let plain = ewm_mean(xs, 0.1, true, 1);
+        assert_eq!(extended.values().as_slice(), &reference);
+
+        let plain_error = plain
+            .values()
+            .iter()
+            .zip(reference)
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f64>();
+        assert!(plain_error > 0.0);
+    }
+}
@@ -0,0 +1,109 @@
+use std::ops::AddAssign;
+
+use arrow::array::PrimitiveArray;
+use arrow::types::NativeType;
+use num::Float;
+
+use super::collect::CollectEwm;
+use crate::trusted_len::TrustedLen;
+
+fn ewm_extreme<I, T, F>(xs: I, alpha: T, beyond: F) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+    F: Fn(T, T) -> bool,
+{
+    let one_sub_alpha = T::one() - alpha;
+    let mut opt_extreme: Option<T> = None;
+
+    xs.into_iter()
+        .map(|opt_x| {
+            if let Some(x) = opt_x {
+                opt_extreme = Some(match opt_extreme {
+                    Some(prev) if !beyond(x, prev) => one_sub_alpha * prev + alpha * x,
+                    _ => x,
+                });
+            }
+            opt_extreme
+        })
+        .collect_ewm()
+}
+
+/// Exponentially weighted moving minimum: a floor that follows dips immediately and
+/// relaxes upwards towards the input otherwise.
+///
+/// The recurrence is
+///
+/// ```text
+/// m[0] = x[0]
+/// m[i] = min(x[i], (1 - alpha) * m[i-1] + alpha * x[i])
+/// ```
+///
+/// i.e. a new low is taken verbatim, while above the floor the gap to the input
+/// shrinks by a factor `1 - alpha` every step. Nulls carry the floor forward.
+pub fn ewm_min<I, T>(xs: I, alpha: T) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    ewm_extreme(xs, alpha, |x, prev| x < prev)
+}
+
+/// Exponentially weighted moving maximum, the mirror image of [`ewm_min`]:
+///
+/// ```text
+/// m[0] = x[0]
+/// m[i] = max(x[i], (1 - alpha) * m[i-1] + alpha * x[i])
+/// ```
+pub fn ewm_max<I, T>(xs: I, alpha: T) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    ewm_extreme(xs, alpha, |x, prev| x > prev)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ewm_min_dip_then_rise() {
+        let xs = vec![
+            Some(8.0f64),
+            Some(4.0),
+            Some(1.0),
+            None,
+            Some(5.0),
+            Some(5.0),
+            Some(0.5),
+        ];
+        let result = ewm_min(xs, 0.5);
+        let expected = PrimitiveArray::from([
+            Some(8.0),
+            Some(4.0),
+            Some(1.0),
+            Some(1.0),
+            Some(3.0),
+            Some(4.0),
+            Some(0.5),
+        ]);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_ewm_max_mirrors_min() {
+        let xs = vec![Some(1.0f64), Some(-3.0), None, Some(2.0), Some(-1.0)];
+        let negated = xs.iter().map(|x| x.map(|x| -x)).collect::<Vec<_>>();
+
+        let max = ewm_max(xs, 0.25);
+        let min = ewm_min(negated, 0.25);
+        assert!(max
+            .iter()
+            .zip(min.iter())
+            .all(|(a, b)| a.copied() == b.map(|b| -b)));
+    }
+}
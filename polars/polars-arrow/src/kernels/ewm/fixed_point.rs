@@ -0,0 +1,249 @@
+use std::ops::AddAssign;
+
+use arrow::array::PrimitiveArray;
+use arrow::types::NativeType;
+use num::{Float, Integer};
+
+use super::collect::CollectEwm;
+use super::EwmMeanState;
+use crate::error::{PolarsError, Result};
+use crate::trusted_len::TrustedLen;
+
+/// Exponentially weighted mean in signed `Qm.n` fixed point, e.g. for exporting the
+/// smoother to embedded targets without a floating point unit.
+///
+/// `xs` and the output are `Qm.n` numbers with `m` integer and `n` fractional bits,
+/// `alpha_q` is `alpha` with `n` fractional bits. The unadjusted recurrence
+/// `mean += alpha * (x - mean)` is evaluated with round-to-nearest and the result
+/// saturates at the bounds of `Qm.n`. Nulls carry the previous mean forward.
+///
+/// # Panics
+/// Panics if `n == 0`, `m + n > 31` or `alpha_q` is not in `(0, 1]`.
+pub fn ewm_mean_qformat<I>(
+    xs: I,
+    alpha_q: i32,
+    m: u32,
+    n: u32,
+    min_periods: usize,
+) -> PrimitiveArray<i32>
+where
+    I: IntoIterator<Item = Option<i32>>,
+    I::IntoIter: TrustedLen,
+{
+    assert!(n > 0 && m + n <= 31, "Qm.n format must fit in an i32");
+    assert!(
+        alpha_q > 0 && alpha_q as i64 <= 1i64 << n,
+        "alpha must be in the interval (0, 1]"
+    );
+    let max = (1i64 << (m + n)) - 1;
+    let min = -(1i64 << (m + n));
+    let half = 1i64 << (n - 1);
+
+    let mut opt_mean: Option<i64> = None;
+    let mut non_null_cnt = 0usize;
+
+    xs.into_iter()
+        .map(|opt_x| {
+            if let Some(x) = opt_x {
+                non_null_cnt += 1;
+                let x = (x as i64).clamp(min, max);
+                let mean = match opt_mean {
+                    None => x,
+                    Some(prev) => {
+                        let step = (alpha_q as i64 * (x - prev) + half) >> n;
+                        (prev + step).clamp(min, max)
+                    }
+                };
+                opt_mean = Some(mean);
+            }
+            match non_null_cnt < min_periods {
+                true => None,
+                false => opt_mean.map(|mean| mean as i32),
+            }
+        })
+        .collect_ewm()
+}
+
+/// What [`ewm_mean_rounded`] does with a mean outside the range of `i64`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum IntOverflow {
+    /// Return an error.
+    #[default]
+    Raise,
+    /// Saturate at `i64::MIN` or `i64::MAX`.
+    Saturate,
+}
+
+/// [`ewm_mean`](super::ewm_mean) rounded to the nearest integer.
+///
+/// The conversion is checked: a rounded mean outside the range of `i64` is handled
+/// according to `overflow`. A `NaN` mean has no integer value and is null.
+pub fn ewm_mean_rounded<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    overflow: IntOverflow,
+) -> Result<PrimitiveArray<i64>>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+    xs.into_iter()
+        .map(|opt_x| {
+            let mean = match state.update(opt_x) {
+                Some(mean) if !mean.is_nan() => mean.round(),
+                _ => return Ok(None),
+            };
+            match (mean.to_i64(), overflow) {
+                (Some(v), _) => Ok(Some(v)),
+                (None, IntOverflow::Saturate) if mean > T::zero() => Ok(Some(i64::MAX)),
+                (None, IntOverflow::Saturate) => Ok(Some(i64::MIN)),
+                (None, IntOverflow::Raise) => Err(PolarsError::ComputeError(
+                    "rounded ewm_mean does not fit in an i64".into(),
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Unadjusted exponentially weighted mean of event counts in exact rational arithmetic.
+///
+/// `alpha = alpha_num / alpha_den`, and every output is a reduced fraction
+/// `(numerator, denominator)` with a positive denominator, free of any rounding.
+/// The denominators grow with every step unless they cancel, so this is meant for
+/// short series.
+///
+/// # Panics
+/// Panics if `alpha` is not in `(0, 1]` or an intermediate result overflows `i128`.
+pub fn ewm_count_rational(events: &[u64], alpha_num: u64, alpha_den: u64) -> Vec<(i128, i128)> {
+    assert!(
+        alpha_num > 0 && alpha_num <= alpha_den,
+        "alpha must be in the interval (0, 1]"
+    );
+    let (a, b) = (alpha_num as i128, alpha_den as i128);
+    let overflow = "ewm_count_rational overflowed an i128";
+
+    let mut opt_mean: Option<(i128, i128)> = None;
+    events
+        .iter()
+        .map(|&x| {
+            let x = x as i128;
+            // mean + a / b * (x - mean) = ((b - a) * p + a * x * q) / (b * q)
+            let (p, q) = match opt_mean {
+                None => (x, 1),
+                Some((p, q)) => {
+                    let p = (b - a)
+                        .checked_mul(p)
+                        .zip(a.checked_mul(x).and_then(|ax| ax.checked_mul(q)))
+                        .and_then(|(l, r)| l.checked_add(r))
+                        .expect(overflow);
+                    let q = b.checked_mul(q).expect(overflow);
+                    let gcd = p.gcd(&q).max(1);
+                    (p / gcd, q / gcd)
+                }
+            };
+            opt_mean = Some((p, q));
+            (p, q)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kernels::ewm::ewm_mean;
+
+    #[test]
+    fn test_ewm_mean_qformat() {
+        // Q7.8
+        let (m, n) = (7, 8);
+        let scale = (1 << n) as f64;
+        let alpha = 0.25;
+
+        let xs = vec![
+            Some(1.5f64),
+            Some(-2.25),
+            None,
+            Some(10.0),
+            Some(3.125),
+            Some(0.5),
+        ];
+        let xs_q = xs
+            .iter()
+            .map(|x| x.map(|x| (x * scale).round() as i32))
+            .collect::<Vec<_>>();
+
+        let expected = ewm_mean(xs, alpha, false, 1);
+        let result = ewm_mean_qformat(xs_q, (alpha * scale) as i32, m, n, 1);
+
+        // every step rounds by at most half an lsb, which decays with 1 - alpha
+        let tolerance = 0.5 / alpha / scale;
+        for (a, b) in result.values().iter().zip(expected.values().iter()) {
+            assert!((*a as f64 / scale - b).abs() <= tolerance);
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_qformat_saturates() {
+        // Q3.4 covers [-8, 8)
+        let xs = vec![Some(100), Some(i32::MAX), Some(i32::MIN)];
+        let result = ewm_mean_qformat(xs, 1 << 4, 3, 4, 1);
+        assert_eq!(result.values().as_slice(), &[100, 127, -128]);
+    }
+
+    #[test]
+    fn test_ewm_mean_qformat_q0_31() {
+        // Q0.31 covers [-1, 1), the largest representable alpha is 1 - 2^-31
+        let scale = (1u64 << 31) as f64;
+        let xs = vec![Some(i32::MAX), Some(0), None, Some(-(1 << 30))];
+        let result = ewm_mean_qformat(xs, 1 << 30, 0, 31, 1);
+        let expected = ewm_mean(vec![Some(1.0), Some(0.0), None, Some(-0.5)], 0.5, false, 1);
+        for (a, b) in result.values().iter().zip(expected.values().iter()) {
+            assert!((*a as f64 / scale - b).abs() <= 2.0 / scale);
+        }
+        assert_eq!(
+            ewm_mean_qformat(vec![Some(5)], i32::MAX, 0, 31, 1).value(0),
+            5
+        );
+    }
+
+    #[test]
+    fn test_ewm_mean_rounded_overflow() {
+        let xs = vec![Some(1.4f64), None, Some(2.6), Some(1e19), Some(-1e30)];
+        let raise = ewm_mean_rounded(xs[..3].to_vec(), 0.5, false, 1, IntOverflow::Raise);
+        assert_eq!(
+            raise.unwrap(),
+            PrimitiveArray::from([Some(1), Some(1), Some(2)])
+        );
+
+        // the mean after 1e19 is about 5e18 and still fits, -1e30 does not
+        let raise = ewm_mean_rounded(xs.clone(), 0.5, false, 1, IntOverflow::Raise);
+        assert!(raise.is_err());
+
+        let saturate = ewm_mean_rounded(xs.clone(), 0.5, false, 1, IntOverflow::Saturate).unwrap();
+        assert_eq!(saturate.value(4), i64::MIN);
+        let xs = vec![Some(1e19f64), Some(f64::NAN)];
+        let saturate = ewm_mean_rounded(xs, 0.5, false, 1, IntOverflow::Saturate).unwrap();
+        assert_eq!(saturate, PrimitiveArray::from([Some(i64::MAX), None]));
+    }
+
+    #[test]
+    fn test_ewm_count_rational() {
+        let events = [3u64, 0, 1, 5, 0, 0, 2, 7, 1];
+        let out = ewm_count_rational(&events, 1, 4);
+        assert_eq!(out[..3], [(3, 1), (9, 4), (31, 16)]);
+
+        let xs = events.iter().map(|&x| Some(x as f64)).collect::<Vec<_>>();
+        let expected = ewm_mean(xs, 0.25, false, 1);
+        for (&(p, q), e) in out.iter().zip(expected.values().iter()) {
+            assert_eq!(p.gcd(&q), 1);
+            assert!((p as f64 / q as f64 - e).abs() < 1e-12);
+        }
+
+        // the denominators cancel with `alpha=1/2` on even counts
+        assert_eq!(ewm_count_rational(&[2, 4], 1, 2), [(2, 1), (3, 1)]);
+    }
+}
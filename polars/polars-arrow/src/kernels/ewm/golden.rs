@@ -0,0 +1,253 @@
+//! Golden outputs of the ewm kernels, generated once on x86_64 and checked on every
+//! platform to catch float behavior that differs between targets.
+
+use super::*;
+
+const MAX_ULPS: u64 = 8;
+const ALPHA: f64 = 0.3;
+
+fn input_f64() -> Vec<Option<f64>> {
+    vec![
+        Some(1.0),
+        Some(5.0),
+        Some(7.0),
+        None,
+        Some(1.0),
+        Some(2.0),
+        Some(1.0),
+        Some(4.0),
+        None,
+        Some(3.0),
+    ]
+}
+
+fn input_f32() -> Vec<Option<f32>> {
+    input_f64().iter().map(|x| x.map(|x| x as f32)).collect()
+}
+
+// (adjust, output)
+const MEAN_F64: [(bool, [f64; 10]); 2] = [
+    (
+        false,
+        [
+            1.0,
+            2.2,
+            3.64,
+            3.64,
+            2.848,
+            2.5936,
+            2.11552,
+            2.680864,
+            2.680864,
+            2.7766048000000003,
+        ],
+    ),
+    (
+        true,
+        [
+            1.0,
+            3.3529411764705883,
+            5.018264840182648,
+            5.018264840182648,
+            3.431898934070272,
+            2.9155457790919908,
+            2.264258781369319,
+            2.8317134815757323,
+            2.8317134815757323,
+            2.885287895449767,
+        ],
+    ),
+];
+
+const MEAN_F32: [(bool, [f32; 10]); 2] = [
+    (
+        false,
+        [
+            1.0, 2.2, 3.64, 3.64, 2.848, 2.5936, 2.11552, 2.680864, 2.680864, 2.776605,
+        ],
+    ),
+    (
+        true,
+        [
+            1.0, 3.3529413, 5.018265, 5.018265, 3.4318986, 2.9155455, 2.2642586, 2.8317134,
+            2.8317134, 2.8852878,
+        ],
+    ),
+];
+
+// (adjust, bias, output)
+const VAR_F64: [(bool, bool, [f64; 10]); 4] = [
+    (
+        false,
+        false,
+        [
+            0.0,
+            7.999999999999999,
+            11.48993288590604,
+            11.48993288590604,
+            8.940986070169352,
+            6.054779085254542,
+            4.776526273650443,
+            4.213006839760907,
+            4.213006839760907,
+            2.954293265601814,
+        ],
+    ),
+    (
+        false,
+        true,
+        [
+            0.0,
+            3.36,
+            7.1903999999999995,
+            7.1903999999999995,
+            6.496895999999999,
+            4.698839039999998,
+            3.8224951295999983,
+            3.421512213503999,
+            3.421512213503999,
+            2.416446584616959,
+        ],
+    ),
+    (
+        true,
+        false,
+        [
+            0.0,
+            8.0,
+            8.456621004566209,
+            8.456621004566209,
+            10.013422818791945,
+            6.688696462829982,
+            5.337138491349483,
+            4.3597187480281745,
+            4.3597187480281745,
+            2.942087443720286,
+        ],
+    ),
+    (
+        true,
+        true,
+        [
+            0.0,
+            3.875432525951557,
+            5.406059089677029,
+            5.406059089677029,
+            7.129689129935697,
+            5.0314164009459965,
+            4.144126367179158,
+            3.4522634939657957,
+            3.4522634939657957,
+            2.359372823696213,
+        ],
+    ),
+];
+
+const VAR_F32: [(bool, bool, [f32; 10]); 4] = [
+    (
+        false,
+        false,
+        [
+            0.0, 8.0, 11.489934, 11.489934, 8.940987, 6.054779, 4.7765265, 4.213007, 4.213007,
+            2.954293,
+        ],
+    ),
+    (
+        false,
+        true,
+        [
+            0.0, 3.3600001, 7.1904006, 7.1904006, 6.4968963, 4.698839, 3.8224952, 3.4215121,
+            3.4215121, 2.4164464,
+        ],
+    ),
+    (
+        true,
+        false,
+        [
+            0.0, 8.000001, 8.45662, 8.45662, 10.013423, 6.688695, 5.337137, 4.359718, 4.359718,
+            2.9420867,
+        ],
+    ),
+    (
+        true,
+        true,
+        [
+            0.0, 3.8754325, 5.406059, 5.406059, 7.1296883, 5.0314155, 4.1441255, 3.4522629,
+            3.4522629, 2.3593724,
+        ],
+    ),
+];
+
+/// Uniform noise in `[0, 1)` from a xorshift generator, reproducible on every platform.
+pub(super) fn uniform_noise(mut seed: u64) -> impl Iterator<Item = f64> {
+    std::iter::repeat_with(move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed >> 11) as f64 / (1u64 << 53) as f64
+    })
+}
+
+fn ulps_f64(a: f64, b: f64) -> u64 {
+    (a.to_bits() as i64).abs_diff(b.to_bits() as i64)
+}
+
+fn ulps_f32(a: f32, b: f32) -> u64 {
+    (a.to_bits() as i32).abs_diff(b.to_bits() as i32) as u64
+}
+
+#[test]
+fn test_ewm_mean_golden() {
+    for (adjust, golden) in MEAN_F64 {
+        let result = ewm_mean(input_f64(), ALPHA, adjust, 1);
+        for (a, b) in result.values().iter().zip(golden) {
+            assert!(ulps_f64(*a, b) <= MAX_ULPS, "adjust={adjust}: {a} != {b}");
+        }
+    }
+    for (adjust, golden) in MEAN_F32 {
+        let result = ewm_mean(input_f32(), ALPHA as f32, adjust, 1);
+        for (a, b) in result.values().iter().zip(golden) {
+            assert!(ulps_f32(*a, b) <= MAX_ULPS, "adjust={adjust}: {a} != {b}");
+        }
+    }
+}
+
+/// `ewm_mean` only uses correctly rounded basic operations in a fixed order, and Rust
+/// never contracts them into FMA, so its output must match to the bit on every target.
+#[test]
+fn test_ewm_mean_golden_bit_exact() {
+    for (adjust, golden) in MEAN_F64 {
+        let result = ewm_mean(input_f64(), ALPHA, adjust, 1);
+        for (a, b) in result.values().iter().zip(golden) {
+            assert_eq!(a.to_bits(), b.to_bits(), "adjust={adjust}: {a} != {b}");
+        }
+    }
+    for (adjust, golden) in MEAN_F32 {
+        let result = ewm_mean(input_f32(), ALPHA as f32, adjust, 1);
+        for (a, b) in result.values().iter().zip(golden) {
+            assert_eq!(a.to_bits(), b.to_bits(), "adjust={adjust}: {a} != {b}");
+        }
+    }
+}
+
+#[test]
+fn test_ewm_var_golden() {
+    for (adjust, bias, golden) in VAR_F64 {
+        let result = ewm_var(input_f64(), ALPHA, adjust, bias, 1);
+        for (a, b) in result.values().iter().zip(golden) {
+            assert!(
+                ulps_f64(*a, b) <= MAX_ULPS,
+                "adjust={adjust}, bias={bias}: {a} != {b}"
+            );
+        }
+    }
+    for (adjust, bias, golden) in VAR_F32 {
+        let result = ewm_var(input_f32(), ALPHA as f32, adjust, bias, 1);
+        for (a, b) in result.values().iter().zip(golden) {
+            assert!(
+                ulps_f32(*a, b) <= MAX_ULPS,
+                "adjust={adjust}, bias={bias}: {a} != {b}"
+            );
+        }
+    }
+}
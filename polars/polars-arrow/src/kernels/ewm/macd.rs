@@ -0,0 +1,87 @@
+use std::ops::AddAssign;
+
+use arrow::array::PrimitiveArray;
+use arrow::types::NativeType;
+use num::Float;
+
+use super::collect::CollectEwm;
+use super::EwmMeanState;
+use crate::trusted_len::TrustedLen;
+
+/// Compute the difference between a fast and a slow exponentially weighted mean
+/// (the MACD line) in a single pass.
+///
+/// If `signal_alpha` is given, the signal line (the exponentially weighted mean of the
+/// MACD line) is returned as well. The MACD line is null wherever one of the two
+/// means is null.
+pub fn ewm_macd<I, T>(
+    xs: I,
+    fast_alpha: T,
+    slow_alpha: T,
+    signal_alpha: Option<T>,
+    adjust: bool,
+    min_periods: usize,
+) -> (PrimitiveArray<T>, Option<PrimitiveArray<T>>)
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    let mut fast = EwmMeanState::new(fast_alpha, adjust, min_periods);
+    let mut slow = EwmMeanState::new(slow_alpha, adjust, min_periods);
+    let mut signal = signal_alpha.map(|alpha| {
+        (
+            EwmMeanState::new(alpha, adjust, min_periods),
+            Vec::with_capacity(xs.size_hint().0),
+        )
+    });
+
+    let macd = xs
+        .map(|opt_x| {
+            let opt_diff = match (fast.update(opt_x), slow.update(opt_x)) {
+                (Some(fast), Some(slow)) => Some(fast - slow),
+                _ => None,
+            };
+            if let Some((state, out)) = signal.as_mut() {
+                out.push(state.update(opt_diff));
+            }
+            opt_diff
+        })
+        .collect_ewm();
+    (macd, signal.map(|(_, out)| PrimitiveArray::from(out)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kernels::ewm::ewm_mean;
+
+    #[test]
+    fn test_ewm_macd() {
+        let xs = vec![
+            Some(2.0f64),
+            Some(3.0),
+            None,
+            Some(5.0),
+            Some(7.0),
+            Some(4.0),
+            None,
+            Some(1.0),
+        ];
+        let (macd, signal) = ewm_macd(xs.clone(), 0.5, 0.2, Some(0.3), true, 2);
+
+        let fast = ewm_mean(xs.clone(), 0.5, true, 2);
+        let slow = ewm_mean(xs, 0.2, true, 2);
+        let expected = fast
+            .iter()
+            .zip(slow.iter())
+            .map(|(f, s)| Some(f? - s?))
+            .collect::<Vec<_>>();
+        assert_eq!(macd, PrimitiveArray::from(expected.clone()));
+        assert_eq!(signal.unwrap(), ewm_mean(expected, 0.3, true, 2));
+
+        let (_, signal) = ewm_macd(vec![Some(1.0f64)], 0.5, 0.2, None, true, 1);
+        assert!(signal.is_none());
+    }
+}
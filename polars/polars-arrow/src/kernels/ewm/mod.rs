@@ -1,8 +1,68 @@
+mod adaptive;
+mod array;
 mod average;
+mod builder;
+mod categorical;
+mod chunked;
+mod collect;
+mod complex;
+mod correlation;
+mod diagnostics;
+mod extended;
+mod extrema;
+mod fixed_point;
+#[cfg(test)]
+mod golden;
+mod macd;
+mod parallel;
+mod rolling;
+mod state;
+mod time;
 mod variance;
+mod wilder;
 
+use std::fmt::Display;
+
+pub use adaptive::*;
+pub use array::*;
 pub use average::*;
+pub use builder::*;
+pub use categorical::*;
+pub use chunked::*;
+pub use complex::*;
+pub use correlation::*;
+pub use diagnostics::*;
+pub use extended::*;
+pub use extrema::*;
+pub use fixed_point::*;
+pub use macd::*;
+use num::Float;
+pub use parallel::*;
+pub use rolling::*;
+pub use state::*;
+pub use time::*;
 pub use variance::*;
+pub use wilder::*;
+
+use crate::error::{PolarsError, Result};
+
+/// Check that `alpha` is a valid smoothing factor, i.e. finite and in `(0, 1]`.
+///
+/// The kernels themselves do not validate `alpha`; a `NaN` alpha, e.g. from a bad span,
+/// would silently produce `NaN` output.
+pub fn validate_alpha<T: Float + Display>(alpha: T) -> Result<()> {
+    if !alpha.is_finite() {
+        return Err(PolarsError::ComputeError(
+            format!("alpha must be finite, got {alpha}").into(),
+        ));
+    }
+    if alpha <= T::zero() || alpha > T::one() {
+        return Err(PolarsError::ComputeError(
+            format!("alpha must satisfy: 0 < alpha <= 1, got {alpha}").into(),
+        ));
+    }
+    Ok(())
+}
 
 #[derive(Debug, Copy, Clone)]
 #[must_use]
@@ -11,6 +71,9 @@ pub struct EWMOptions {
     pub adjust: bool,
     pub bias: bool,
     pub min_periods: usize,
+    /// Set the output to null wherever the input is null, instead of carrying
+    /// the previous value forward.
+    pub mask_output_to_input_validity: bool,
 }
 
 impl Default for EWMOptions {
@@ -20,6 +83,7 @@ impl Default for EWMOptions {
             adjust: true,
             bias: false,
             min_periods: 1,
+            mask_output_to_input_validity: false,
         }
     }
 }
@@ -33,6 +97,10 @@ impl EWMOptions {
         self.adjust = adjust;
         self
     }
+    pub fn and_mask_output_to_input_validity(mut self, mask: bool) -> Self {
+        self.mask_output_to_input_validity = mask;
+        self
+    }
     pub fn and_span(mut self, span: usize) -> Self {
         assert!(span >= 1);
         self.alpha = 2.0 / (span as f64 + 1.0);
@@ -41,7 +109,7 @@ impl EWMOptions {
 
     pub fn and_half_life(mut self, half_life: f64) -> Self {
         assert!(half_life > 0.0);
-        self.alpha = 1.0 - ((-2.0f64).ln() / half_life).exp();
+        self.alpha = 1.0 - (-(2.0f64.ln()) / half_life).exp();
         self
     }
 
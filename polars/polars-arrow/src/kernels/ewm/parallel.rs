@@ -0,0 +1,129 @@
+use std::ops::AddAssign;
+use std::thread;
+
+use arrow::array::PrimitiveArray;
+use arrow::types::NativeType;
+use num::{Float, NumCast};
+
+use super::{EWMOptions, EwmChunkedScan};
+
+/// Evaluate `f` for every index in `0..n` on `n_threads` threads, each taking a
+/// contiguous range of indices, and return the results in order.
+fn map_in_threads<R, F>(n: usize, n_threads: usize, f: F) -> Vec<R>
+where
+    R: Send,
+    F: Fn(usize) -> R + Sync,
+{
+    let per_thread = std::cmp::max((n + n_threads - 1) / n_threads, 1);
+    let f = &f;
+    thread::scope(|s| {
+        let handles = (0..n)
+            .step_by(per_thread)
+            .map(|start| {
+                let end = std::cmp::min(start + per_thread, n);
+                s.spawn(move || (start..end).map(f).collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// [`ewm_mean`](super::ewm_mean) computed as an [`EwmChunkedScan`] on `n_threads`
+/// threads.
+///
+/// The blocks are summarized and the chunks computed in parallel, with one chunk per
+/// thread. The output does not depend on `n_threads` and equals
+/// [`ewm_mean_chunked`](super::ewm_mean_chunked); it is close to the serial
+/// [`ewm_mean`](super::ewm_mean) but not bit-identical. Only `alpha`, `adjust` and
+/// `min_periods` of `options` are used.
+///
+/// # Panics
+/// Panics if `n_threads == 0`.
+pub fn ewm_mean_parallel<T>(
+    values: &[Option<T>],
+    options: EWMOptions,
+    n_threads: usize,
+) -> PrimitiveArray<T>
+where
+    T: Float + NativeType + AddAssign,
+{
+    assert!(n_threads > 0, "n_threads must be positive");
+    let chunk_size = std::cmp::max((values.len() + n_threads - 1) / n_threads, 1);
+    let mut scan = EwmChunkedScan::new(
+        values,
+        NumCast::from(options.alpha).unwrap(),
+        options.adjust,
+        options.min_periods,
+        chunk_size,
+    );
+
+    let summaries = map_in_threads(scan.n_blocks(), n_threads, |b| scan.block_summary(b));
+    scan.seed(&summaries);
+
+    let chunks = scan.chunks().collect::<Vec<_>>();
+    let out = map_in_threads(chunks.len(), n_threads, |c| {
+        scan.compute_chunk(chunks[c].clone())
+    });
+    PrimitiveArray::from(out.into_iter().flatten().collect::<Vec<_>>())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kernels::ewm::{ewm_mean, ewm_mean_chunked};
+
+    #[test]
+    fn test_ewm_mean_parallel() {
+        let mut xs = (0..10_000)
+            .map(|i| match i % 11 {
+                4 => None,
+                _ => Some(((i * 53) % 97) as f64 - 48.0),
+            })
+            .collect::<Vec<_>>();
+        xs[..1500].iter_mut().for_each(|x| *x = None);
+
+        let bits = |arr: &PrimitiveArray<f64>| {
+            arr.iter()
+                .map(|x| x.map(|x| x.to_bits()))
+                .collect::<Vec<_>>()
+        };
+        for adjust in [false, true] {
+            let options = EWMOptions::default()
+                .and_span(300)
+                .and_adjust(adjust)
+                .and_min_periods(3);
+            let out = ewm_mean_parallel(&xs, options, 1);
+
+            // within 1e-10 of the serial `ewm_mean`
+            let serial = ewm_mean(xs.iter().copied(), options.alpha, adjust, 3);
+            assert_eq!(out.len(), serial.len());
+            for (a, b) in out.iter().zip(serial.iter()) {
+                match (a, b) {
+                    (Some(a), Some(b)) => assert!((a - b).abs() < 1e-10, "{a} != {b}"),
+                    (a, b) => assert_eq!(a, b),
+                }
+            }
+
+            // and bit-identical for every number of threads
+            let chunked = ewm_mean_chunked(&xs, options.alpha, adjust, 3, 1);
+            assert_eq!(bits(&out), bits(&chunked));
+            for n_threads in [2, 3, 7, 64] {
+                let other = ewm_mean_parallel(&xs, options, n_threads);
+                assert_eq!(bits(&out), bits(&other));
+            }
+        }
+
+        let xs = vec![None, Some(1.0), None, Some(3.0)];
+        assert_eq!(
+            ewm_mean_parallel(&xs, EWMOptions::default(), 4),
+            ewm_mean(xs.iter().copied(), 0.5, true, 1)
+        );
+        assert_eq!(
+            ewm_mean_parallel::<f64>(&[], EWMOptions::default(), 4).len(),
+            0
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::iter::Sum;
+use std::ops::{AddAssign, SubAssign};
+
+use arrow::array::PrimitiveArray;
+use arrow::types::NativeType;
+use num::pow::Pow;
+use num::Float;
+
+use super::collect::CollectEwm;
+use super::EwmMeanState;
+use crate::data_types::IsFloat;
+use crate::kernels::rolling::no_nulls::{RollingAggWindowNoNulls, StdWindow};
+
+/// [`ewm_mean`](super::ewm_mean) of the trailing rolling std of `values`, in a single
+/// pass.
+///
+/// This equals `rolling_std(values, window_size, rolling_min_periods, false, None)`
+/// followed by `ewm_mean` over its output, without materializing the rolling std.
+/// Positions where the window holds fewer than `rolling_min_periods` values are null
+/// in the rolling std, and therefore skipped by the average.
+///
+/// # Panics
+/// Panics if `window_size == 0`.
+pub fn ewm_of_rolling_std<T>(
+    values: &[T],
+    window_size: usize,
+    rolling_min_periods: usize,
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    T: NativeType + Float + IsFloat + Sum + AddAssign + SubAssign + Pow<T, Output = T>,
+{
+    assert!(window_size > 0, "window_size must be positive");
+    let mut window = StdWindow::new(values, 0, std::cmp::min(1, values.len()));
+    let mut state = EwmMeanState::new(alpha, adjust, min_periods);
+
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, _)| {
+            let (start, end) = (i.saturating_sub(window_size - 1), i + 1);
+            // safety: `start <= end <= values.len()`
+            let std = unsafe { window.update(start, end) };
+            let opt_std = (end - start >= rolling_min_periods).then_some(std);
+            state.update(opt_std)
+        })
+        .collect_ewm()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kernels::ewm::ewm_mean;
+    use crate::kernels::rolling::no_nulls::rolling_std;
+
+    #[test]
+    fn test_ewm_of_rolling_std() {
+        let values = (0..300)
+            .map(|i| ((i * 37) % 23) as f64 * 0.5 - 3.0)
+            .collect::<Vec<_>>();
+        for (window_size, rolling_min_periods) in [(1, 1), (5, 1), (5, 3), (20, 20)] {
+            for adjust in [false, true] {
+                let out =
+                    ewm_of_rolling_std(&values, window_size, rolling_min_periods, 0.1, adjust, 2);
+
+                let std = rolling_std(&values, window_size, rolling_min_periods, false, None);
+                let std = std.as_any().downcast_ref::<PrimitiveArray<f64>>().unwrap();
+                let expected = ewm_mean(std.iter().map(|v| v.copied()), 0.1, adjust, 2);
+                assert_eq!(out, expected);
+            }
+        }
+        assert_eq!(
+            ewm_of_rolling_std(&[] as &[f64], 3, 1, 0.5, true, 1).len(),
+            0
+        );
+    }
+}
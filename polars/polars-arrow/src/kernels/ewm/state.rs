@@ -0,0 +1,374 @@
+use std::ops::AddAssign;
+
+use arrow::array::PrimitiveArray;
+use arrow::types::NativeType;
+use num::Float;
+
+use super::collect::CollectEwm;
+use crate::trusted_len::TrustedLen;
+
+/// One observation `x` of the [`ewm_mean`](super::ewm_mean) recurrence with smoothing
+/// factor `alpha`. Returns the mean and the sum of the weights after `x`.
+#[cfg_attr(not(feature = "min-size"), inline)]
+pub(super) fn ewm_mean_step<T: Float>(opt_mean: Option<T>, wgt_sum: T, x: T, alpha: T) -> (T, T) {
+    // To prevent numerical instability (and as a slight optimization), we
+    // special-case `alpha=1`.
+    if alpha.is_one() {
+        return (x, T::one());
+    }
+    let prev_mean = opt_mean.unwrap_or(x);
+    let wgt_sum = (T::one() - alpha) * wgt_sum + alpha;
+
+    // A zero `wgt_sum` (only possible with `alpha=0` and `adjust=true`) gives the
+    // observation no weight; guard against dividing by it. An observation equal to the
+    // mean leaves it unchanged, which keeps a constant input exactly constant
+    // (`-0.0 + 0.0` would otherwise turn a `-0.0` into `0.0`).
+    let mean = if wgt_sum.is_zero() || x == prev_mean {
+        prev_mean
+    } else {
+        prev_mean + (x - prev_mean) * alpha / wgt_sum
+    };
+    (mean, wgt_sum)
+}
+
+/// State of the [`ewm_mean`](super::ewm_mean) recurrence, updated one value at a time.
+///
+/// This allows computing the exponentially weighted mean over data that arrives
+/// incrementally, or running several averages in a single pass.
+#[derive(Debug, Copy, Clone)]
+pub struct EwmMeanState<T> {
+    alpha: T,
+    adjust: bool,
+    min_periods: usize,
+    opt_mean: Option<T>,
+    non_null_cnt: usize,
+    wgt_sum: T,
+}
+
+impl<T: Float + AddAssign> EwmMeanState<T> {
+    pub fn new(alpha: T, adjust: bool, min_periods: usize) -> Self {
+        Self {
+            alpha,
+            adjust,
+            min_periods,
+            opt_mean: None,
+            non_null_cnt: 0,
+            wgt_sum: if adjust { T::zero() } else { T::one() },
+        }
+    }
+
+    /// Start from `seed` as if it had been observed once before the input. The seed does
+    /// not count towards `min_periods`.
+    pub fn with_seed(alpha: T, adjust: bool, min_periods: usize, seed: T) -> Self {
+        Self {
+            opt_mean: Some(seed),
+            wgt_sum: if adjust { alpha } else { T::one() },
+            ..Self::new(alpha, adjust, min_periods)
+        }
+    }
+
+    /// Return to the state right after [`EwmMeanState::new`], keeping the configuration,
+    /// so the state can be reused for another series.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.alpha, self.adjust, self.min_periods);
+    }
+
+    /// Feed the next value and return the output at this position.
+    ///
+    /// An observation equal to the current mean is not treated specially: it leaves the
+    /// mean unchanged, but still counts towards `min_periods` and advances the weights,
+    /// so later observations are weighted as if it were any other value.
+    #[cfg_attr(not(feature = "min-size"), inline)]
+    pub fn update(&mut self, opt_x: Option<T>) -> Option<T> {
+        if let Some(x) = opt_x {
+            self.non_null_cnt += 1;
+            let (mean, wgt_sum) = ewm_mean_step(self.opt_mean, self.wgt_sum, x, self.alpha);
+            self.opt_mean = Some(mean);
+            self.wgt_sum = wgt_sum;
+        }
+        // with `alpha=1` the output is the observation itself, so a null stays null
+        if self.alpha.is_one() && opt_x.is_none() {
+            return None;
+        }
+        self.mean()
+    }
+
+    /// Age the weights by one step without an observation, as if the current mean had
+    /// been observed. The mean itself and the `min_periods` count are unchanged.
+    pub fn decay(&mut self) {
+        if self.opt_mean.is_some() && !self.alpha.is_one() {
+            self.wgt_sum = (T::one() - self.alpha) * self.wgt_sum + self.alpha;
+        }
+    }
+
+    /// Replace the incrementally updated weight sum by its closed form, `1 - (1 - alpha)^n`
+    /// after `n` observations with `adjust` and `1` without, to drop the rounding errors
+    /// it accumulated. Only valid for a state that was fed through
+    /// [`EwmMeanState::update`] alone.
+    pub(super) fn renormalize(&mut self) {
+        if self.opt_mean.is_none() || self.alpha.is_one() {
+            return;
+        }
+        self.wgt_sum = match self.adjust {
+            true => T::one() - (T::one() - self.alpha).powf(T::from(self.non_null_cnt).unwrap()),
+            false => T::one(),
+        };
+    }
+
+    /// Combine with the state `other` of the values that follow, so that `self` becomes
+    /// the state after both.
+    ///
+    /// With normalized weights the state of a block does not depend on what came before
+    /// it, so `other` must have been started with `adjust=true` and the same `alpha`. If
+    /// `self` has not observed a value yet, `other` is taken as is and must have been
+    /// started with the `adjust` of `self` instead. The result equals updating `self`
+    /// with the values of `other`, up to rounding.
+    pub fn merge(&mut self, other: &Self) {
+        let other_mean = match other.opt_mean {
+            Some(mean) => mean,
+            None => return,
+        };
+        self.non_null_cnt += other.non_null_cnt;
+        match self.opt_mean {
+            Some(mean) if !self.alpha.is_one() => {
+                // the weights of `self` decay over the observations of `other`
+                let decay = T::one() - other.wgt_sum;
+                let wgt_sum = decay * self.wgt_sum + other.wgt_sum;
+                self.opt_mean =
+                    Some((decay * self.wgt_sum * mean + other.wgt_sum * other_mean) / wgt_sum);
+                self.wgt_sum = wgt_sum;
+            }
+            _ => {
+                self.opt_mean = Some(other_mean);
+                self.wgt_sum = other.wgt_sum;
+            }
+        }
+    }
+
+    /// The sum of the weights of the observations so far. With `adjust=false` the
+    /// weights are normalized and this is always one.
+    pub fn wgt_sum(&self) -> T {
+        self.wgt_sum
+    }
+
+    /// The current mean, `None` if fewer than `min_periods` values were observed.
+    #[cfg_attr(not(feature = "min-size"), inline)]
+    pub fn mean(&self) -> Option<T> {
+        match self.non_null_cnt < self.min_periods {
+            true => None,
+            false => self.opt_mean,
+        }
+    }
+}
+
+impl<T: Float + NativeType + AddAssign> EwmMeanState<T> {
+    /// Feed the next batch of a stream and return the output for it.
+    ///
+    /// The state, including the running `wgt_sum` of `adjust=true`, carries over to the
+    /// next batch, so the concatenated output equals [`ewm_mean`](super::ewm_mean)
+    /// over the whole stream regardless of how it is split into batches.
+    pub fn update_batch<I>(&mut self, xs: I) -> PrimitiveArray<T>
+    where
+        I: IntoIterator<Item = Option<T>>,
+        I::IntoIter: TrustedLen,
+    {
+        xs.into_iter().map(|opt_x| self.update(opt_x)).collect_ewm()
+    }
+}
+
+/// State of the [`ewm_var`](super::ewm_var) recurrence, updated one value at a time.
+///
+/// The mean is tracked by an [`EwmMeanState`], so the variance kernels center the data
+/// on exactly the output of [`ewm_mean`](super::ewm_mean).
+#[derive(Debug, Copy, Clone)]
+pub struct EwmVarState<T> {
+    mean: EwmMeanState<T>,
+    bias: bool,
+    var: T,
+    wgt_sum_sqr: T,
+}
+
+impl<T: Float + AddAssign> EwmVarState<T> {
+    pub fn new(alpha: T, adjust: bool, bias: bool, min_periods: usize) -> Self {
+        Self {
+            mean: EwmMeanState::new(alpha, adjust, min_periods),
+            bias,
+            var: T::zero(),
+            wgt_sum_sqr: T::zero(),
+        }
+    }
+
+    /// Feed the next value and return the mean at this position, as
+    /// [`EwmMeanState::update`] does.
+    #[cfg_attr(not(feature = "min-size"), inline)]
+    pub fn update(&mut self, opt_x: Option<T>) -> Option<T> {
+        let x = match opt_x {
+            Some(x) => x,
+            None => return self.mean.update(None),
+        };
+        let alpha = self.mean.alpha;
+        let two = T::one() + T::one();
+        let prev_mean = self.mean.opt_mean.unwrap_or(x);
+
+        // without `adjust` the weights are normalized from the first observation on
+        self.wgt_sum_sqr = match self.mean.opt_mean {
+            None if !self.mean.adjust => T::one(),
+            _ => (T::one() - alpha).powf(two) * self.wgt_sum_sqr + alpha.powf(two),
+        };
+        let out = self.mean.update(opt_x);
+
+        let wgt_sum = self.mean.wgt_sum;
+        let wgt = if wgt_sum.is_zero() {
+            T::zero()
+        } else {
+            alpha / wgt_sum
+        };
+        self.var = (T::one() - wgt) * (self.var + wgt * (x - prev_mean).powf(two));
+        out
+    }
+
+    /// The current mean, `None` if fewer than `min_periods` values were observed.
+    #[cfg_attr(not(feature = "min-size"), inline)]
+    pub fn mean(&self) -> Option<T> {
+        self.mean.mean()
+    }
+
+    /// The current variance, `None` if fewer than `min_periods` values were observed.
+    ///
+    /// Without `bias` the variance is divided by `1 - wgt_sum_sqr / wgt_sum^2`. With
+    /// `alpha=1` only the newest observation has weight and this is `NaN` after the
+    /// first observation, as in pandas.
+    #[cfg_attr(not(feature = "min-size"), inline)]
+    pub fn var(&self) -> Option<T> {
+        self.mean()?;
+        // NOTE: the `non_null_cnt == 1` condition prevents a NaN
+        // from appearing in the first entry (it prevents a zero division)
+        let correction = if self.bias || self.mean.non_null_cnt == 1 {
+            T::one()
+        } else {
+            let two = T::one() + T::one();
+            T::one() - self.wgt_sum_sqr / self.mean.wgt_sum.powf(two)
+        };
+        Some(self.var / correction)
+    }
+
+    /// The current standard deviation, `None` if fewer than `min_periods` values were
+    /// observed.
+    #[cfg_attr(not(feature = "min-size"), inline)]
+    pub fn std(&self) -> Option<T> {
+        self.var().map(|var| var.sqrt())
+    }
+
+    /// The sum of the weights of the observations so far, see [`EwmMeanState::wgt_sum`].
+    pub fn wgt_sum(&self) -> T {
+        self.mean.wgt_sum
+    }
+
+    /// The effective sample size `wgt_sum^2 / wgt_sum_sqr` of the weights, `None` before
+    /// the first observation. Unlike the moments it does not wait for `min_periods`.
+    pub fn effective_n(&self) -> Option<T> {
+        let wgt_sum = self.mean.wgt_sum;
+        self.mean
+            .opt_mean
+            .map(|_| wgt_sum * wgt_sum / self.wgt_sum_sqr)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::kernels::ewm::ewm_mean;
+
+    #[test]
+    fn test_ewm_mean_state_reset() {
+        let xs = [Some(3.0f64), None, Some(1.0), Some(4.0), Some(-2.0)];
+        for adjust in [false, true] {
+            let mut state = EwmMeanState::new(0.4, adjust, 2);
+            for x in [Some(100.0), Some(7.0), None] {
+                state.update(x);
+            }
+            state.reset();
+
+            let mut fresh = EwmMeanState::new(0.4, adjust, 2);
+            for x in xs {
+                assert_eq!(state.update(x), fresh.update(x));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_state_single_element_batches() {
+        let xs = vec![
+            None,
+            Some(3.0f64),
+            Some(1.0),
+            None,
+            None,
+            Some(4.0),
+            Some(-2.0),
+            Some(10.0),
+        ];
+        for (alpha, adjust) in [(0.3, true), (0.3, false), (1.0, true)] {
+            let expected = ewm_mean(xs.clone(), alpha, adjust, 2);
+
+            let mut state = EwmMeanState::new(alpha, adjust, 2);
+            let streamed = xs
+                .iter()
+                .flat_map(|&x| state.update_batch(vec![x]).into_iter().collect::<Vec<_>>())
+                .map(|v| v.map(|v| v.to_bits()))
+                .collect::<Vec<_>>();
+            let expected = expected
+                .iter()
+                .map(|v| v.map(|v| v.to_bits()))
+                .collect::<Vec<_>>();
+            assert_eq!(streamed, expected, "alpha={alpha}, adjust={adjust}");
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_state_merge() {
+        let (head, tail) = (
+            [Some(3.0f64), None, Some(1.0)],
+            [Some(4.0), None, Some(-2.0)],
+        );
+        for adjust in [false, true] {
+            let mut state = EwmMeanState::new(0.4, adjust, 1);
+            let mut serial = EwmMeanState::new(0.4, adjust, 1);
+            let mut other = EwmMeanState::new(0.4, true, 1);
+            for x in head {
+                state.update(x);
+                serial.update(x);
+            }
+            for x in tail {
+                other.update(x);
+                serial.update(x);
+            }
+            state.merge(&other);
+            assert!((state.mean().unwrap() - serial.mean().unwrap()).abs() < 1e-14);
+            assert!(
+                (state.update(Some(5.0)).unwrap() - serial.update(Some(5.0)).unwrap()).abs()
+                    < 1e-14
+            );
+        }
+    }
+
+    #[test]
+    fn test_ewm_mean_state_equal_observation() {
+        for adjust in [false, true] {
+            let mut state = EwmMeanState::new(0.5, adjust, 4);
+            assert_eq!(state.update(Some(2.0f64)), None);
+
+            // the mean stays put, but `min_periods` is reached on the fourth observation
+            assert_eq!(state.update(Some(2.0)), None);
+            assert_eq!(state.update(Some(2.0)), None);
+            assert_eq!(state.update(Some(2.0)), Some(2.0));
+            assert_eq!(state.update(Some(2.0)), Some(2.0));
+
+            // and the weights advanced as for any other value: with `adjust=true` the
+            // five observations have a total weight of `31/32` against `1/2` for the next
+            let expected = if adjust { 2.0 + 4.0 * 32.0 / 63.0 } else { 4.0 };
+            let out = state.update(Some(6.0)).unwrap();
+            assert!((out - expected).abs() < 1e-12, "{out} != {expected}");
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::ops::AddAssign;
+
+use arrow::array::PrimitiveArray;
+use arrow::types::NativeType;
+use num::Float;
+
+use super::collect::CollectEwm;
+use crate::error::{PolarsError, Result};
+use crate::trusted_len::TrustedLen;
+
+/// How [`ewm_mean_by_time`] deals with timestamps that are `NaN` or infinite.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum NonFiniteTimes {
+    /// Return an error.
+    #[default]
+    Raise,
+    /// Treat the observation at that position as missing.
+    Skip,
+}
+
+fn check_times<T: Float>(
+    len: usize,
+    times: &[T],
+    half_life: T,
+    non_finite: NonFiniteTimes,
+) -> Result<()> {
+    if len != times.len() {
+        return Err(PolarsError::ComputeError(
+            "times must have the same length as the input".into(),
+        ));
+    }
+    if half_life.is_nan() || half_life <= T::zero() {
+        return Err(PolarsError::ComputeError(
+            "half_life must be positive".into(),
+        ));
+    }
+    if non_finite == NonFiniteTimes::Raise && times.iter().any(|t| !t.is_finite()) {
+        return Err(PolarsError::ComputeError(
+            "times must be finite, found NaN or infinity".into(),
+        ));
+    }
+    // skipped timestamps do not take part in the order
+    let mut finite = times.iter().filter(|t| t.is_finite());
+    if let Some(&first) = finite.next() {
+        let mut prev = first;
+        for &t in finite {
+            if t < prev {
+                return Err(PolarsError::ComputeError(
+                    "times must be sorted in ascending order".into(),
+                ));
+            }
+            prev = t;
+        }
+    }
+    Ok(())
+}
+
+/// The time-aware recurrence over `(observation, time)` pairs, where `elapsed` gives
+/// the time between the previous and the current observation.
+fn ewm_mean_by_time_iter<I, T>(
+    pairs: I,
+    half_life: T,
+    elapsed: fn(T, T) -> T,
+) -> impl TrustedLen<Item = Option<T>>
+where
+    I: TrustedLen<Item = (Option<T>, T)>,
+    T: Float + NativeType + AddAssign,
+{
+    let half = T::from(0.5).unwrap();
+    let mut opt_prev: Option<(T, T)> = None;
+
+    pairs.map(move |(opt_x, t)| {
+        if let (Some(x), true) = (opt_x, t.is_finite()) {
+            let mean = match opt_prev {
+                None => x,
+                Some((prev_mean, prev_t)) => {
+                    let alpha = T::one() - half.powf(elapsed(prev_t, t) / half_life);
+                    prev_mean + (x - prev_mean) * alpha
+                }
+            };
+            opt_prev = Some((mean, t));
+        }
+        opt_prev.map(|(mean, _)| mean)
+    })
+}
+
+/// Time-aware exponentially weighted mean.
+///
+/// An observation `dt` time units after the previous one is weighted with
+/// `alpha = 1 - 0.5^(dt / half_life)`, so the previous mean loses half its weight
+/// every `half_life`. `times` must be sorted in ascending order and `half_life` must be
+/// positive, otherwise an error is returned. Null observations carry the previous mean
+/// forward.
+pub fn ewm_mean_by_time<I, T>(
+    xs: I,
+    times: &[T],
+    half_life: T,
+    non_finite: NonFiniteTimes,
+) -> Result<PrimitiveArray<T>>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let xs = xs.into_iter();
+    check_times(xs.size_hint().0, times, half_life, non_finite)?;
+
+    let pairs = xs.zip(times.iter().copied());
+    Ok(ewm_mean_by_time_iter(pairs, half_life, |prev_t, t| t - prev_t).collect_ewm())
+}
+
+/// Anti-causal [`ewm_mean_by_time`]: the average runs from the latest timestamp
+/// backwards, so each output summarizes the observations at or after it.
+///
+/// `times` must still be sorted in ascending order.
+pub fn ewm_mean_by_reverse<T>(
+    xs: &[Option<T>],
+    times: &[T],
+    half_life: T,
+    non_finite: NonFiniteTimes,
+) -> Result<PrimitiveArray<T>>
+where
+    T: Float + NativeType + AddAssign,
+{
+    check_times(xs.len(), times, half_life, non_finite)?;
+
+    let pairs = xs.iter().copied().zip(times.iter().copied()).rev();
+    Ok(ewm_mean_by_time_iter(pairs, half_life, |prev_t, t| prev_t - t).collect_ewm_reversed())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ewm_mean_by_time() {
+        let xs = vec![Some(1.0f64), Some(3.0), None, Some(5.0)];
+        let times = [0.0, 1.0, 2.0, 3.0];
+        let result = ewm_mean_by_time(xs, &times, 1.0, NonFiniteTimes::Raise).unwrap();
+        // dt == half_life, so every step is an average with alpha = 0.5, and
+        // after the null the elapsed time is two half lives: alpha = 0.75
+        let expected = PrimitiveArray::from([Some(1.0), Some(2.0), Some(2.0), Some(4.25)]);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_ewm_mean_by_time_nan() {
+        let xs = vec![Some(1.0f64), Some(3.0), Some(100.0), Some(5.0)];
+        let times = [0.0, 1.0, f64::NAN, 3.0];
+
+        assert!(ewm_mean_by_time(xs.clone(), &times, 1.0, NonFiniteTimes::Raise).is_err());
+
+        let result = ewm_mean_by_time(xs, &times, 1.0, NonFiniteTimes::Skip).unwrap();
+        let expected = PrimitiveArray::from([Some(1.0), Some(2.0), Some(2.0), Some(4.25)]);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_ewm_mean_by_time_invalid() {
+        let xs = vec![Some(1.0f64), Some(3.0), Some(5.0)];
+        let times = [0.0, 1.0, 2.0];
+        for half_life in [0.0, -1.0, f64::NAN] {
+            let err = ewm_mean_by_time(xs.clone(), &times, half_life, NonFiniteTimes::Raise);
+            assert!(err.unwrap_err().to_string().contains("half_life"));
+            assert!(ewm_mean_by_reverse(&xs, &times, half_life, NonFiniteTimes::Raise).is_err());
+        }
+
+        let unsorted = [0.0, 2.0, 1.0];
+        let err = ewm_mean_by_time(xs.clone(), &unsorted, 1.0, NonFiniteTimes::Raise);
+        assert!(err.unwrap_err().to_string().contains("ascending"));
+        assert!(ewm_mean_by_reverse(&xs, &unsorted, 1.0, NonFiniteTimes::Raise).is_err());
+
+        // equal and skipped timestamps are fine
+        let times = [1.0, 1.0, 2.0];
+        assert!(ewm_mean_by_time(xs.clone(), &times, 1.0, NonFiniteTimes::Raise).is_ok());
+        let times = [1.0, f64::NAN, 2.0];
+        assert!(ewm_mean_by_time(xs, &times, 1.0, NonFiniteTimes::Skip).is_ok());
+    }
+
+    #[test]
+    fn test_ewm_mean_by_reverse() {
+        let xs = vec![Some(1.0f64), None, Some(3.0), Some(-2.0), Some(5.0)];
+        let times = [0.0, 0.5, 1.5, 4.0, 4.25];
+        let result = ewm_mean_by_reverse(&xs, &times, 1.5, NonFiniteTimes::Raise).unwrap();
+
+        // reversed, the timestamps descend; negating them keeps the gaps but makes them
+        // ascend as the forward kernel requires
+        let rev_xs = xs.iter().rev().copied().collect::<Vec<_>>();
+        let rev_times = times.iter().rev().map(|t| -t).collect::<Vec<_>>();
+        let forward = ewm_mean_by_time(rev_xs, &rev_times, 1.5, NonFiniteTimes::Raise).unwrap();
+        let expected = forward.iter().rev().map(|v| v.copied()).collect::<Vec<_>>();
+        assert_eq!(result, PrimitiveArray::from(expected));
+
+        assert!(ewm_mean_by_reverse(&xs, &times[1..], 1.5, NonFiniteTimes::Raise).is_err());
+    }
+}
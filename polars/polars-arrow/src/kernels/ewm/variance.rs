@@ -2,10 +2,11 @@ use std::ops::AddAssign;
 
 use arrow::array::PrimitiveArray;
 use arrow::types::NativeType;
-use num::{Float, One};
+use num::Float;
 
+use super::collect::CollectEwm;
+use super::EwmVarState;
 use crate::trusted_len::TrustedLen;
-use crate::utils::CustomIterTools;
 
 pub fn ewm_std<I, T>(
     xs: I,
@@ -19,125 +20,238 @@ where
     I::IntoIter: TrustedLen,
     T: Float + NativeType + AddAssign,
 {
-    let one_sub_alpha = T::one() - alpha;
-    let two = T::one() + T::one();
+    let mut state = EwmVarState::new(alpha, adjust, bias, min_periods);
+    xs.into_iter()
+        .map(|opt_x| {
+            state.update(opt_x);
+            state.std()
+        })
+        .collect_ewm()
+}
 
-    let mut opt_mean = None;
-    let mut opt_var = None;
-    let mut non_null_cnt = 0usize;
+pub fn ewm_var<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    bias: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut state = EwmVarState::new(alpha, adjust, bias, min_periods);
+    xs.into_iter()
+        .map(|opt_x| {
+            state.update(opt_x);
+            state.var()
+        })
+        .collect_ewm()
+}
 
-    let wgt = alpha;
+/// Standard error of the exponentially weighted mean, `sqrt(var / ess)`.
+///
+/// `ess = wgt_sum^2 / wgt_sum_sqr` is the effective sample size of the weights. Where
+/// [`ewm_std`] measures the spread of the data, this measures the uncertainty of the
+/// smoothed estimate.
+pub fn ewm_sem<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    bias: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut state = EwmVarState::new(alpha, adjust, bias, min_periods);
+    xs.into_iter()
+        .map(|opt_x| {
+            state.update(opt_x);
+            let ess = state.effective_n()?;
+            state.var().map(|var| (var / ess).sqrt())
+        })
+        .collect_ewm()
+}
 
-    let (mut wgt_sum, mut wgt_sum_sqr) = if adjust {
-        (T::zero(), T::zero())
-    } else {
-        // NOTE: we must ensure `wgt_sum` and `wgt_sum_sqr` are equal
-        // to 1 during the first iteration
-        (T::one(), (T::one() + alpha) / one_sub_alpha)
-    };
+/// Ratio of the exponentially weighted variance of the residuals `x - ewm_mean(x)` to
+/// the exponentially weighted variance of the input.
+///
+/// This indicates how much of the variance the smoother removes: the mean and all
+/// variances are unadjusted and biased, using the same `alpha`. The ratio is null
+/// until the input variance is non-zero, and nulls carry the previous ratio forward.
+pub fn ewm_residual_var_ratio<I, T>(xs: I, alpha: T) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    // the input state also provides the mean the residuals are taken from
+    let mut input = EwmVarState::new(alpha, false, true, 1);
+    let mut residual = EwmVarState::new(alpha, false, true, 1);
+    let mut opt_ratio = None;
 
     xs.into_iter()
         .map(|opt_x| {
             if let Some(x) = opt_x {
-                non_null_cnt += 1;
-
-                let prev_mean = opt_mean.unwrap_or(x);
-                let prev_var = opt_var.unwrap_or_else(T::zero);
-
-                wgt_sum = one_sub_alpha * wgt_sum + wgt;
-                wgt_sum_sqr = one_sub_alpha.powf(two) * wgt_sum_sqr + wgt.powf(two);
-
-                let curr_mean = prev_mean + (x - prev_mean) * wgt / wgt_sum;
-                let curr_var = (T::one() - wgt / wgt_sum)
-                    * (prev_var + wgt / wgt_sum * (x - prev_mean).powf(two));
+                let mean = input.update(opt_x).unwrap();
+                residual.update(Some(x - mean));
 
-                opt_mean = Some(curr_mean);
-                opt_var = Some(curr_var);
-            }
-            match non_null_cnt < min_periods {
-                true => None,
-                false => opt_var.map(|var| {
-                    // NOTE: the `non_null_cnt.is_one()` condition prevents a NaN
-                    // from appearing in the first entry (it prevents a zero division)
-                    let correction = if bias || non_null_cnt.is_one() {
-                        T::one()
-                    } else {
-                        T::one() - wgt_sum_sqr / wgt_sum.powf(two)
-                    };
-                    (var / correction).sqrt()
-                }),
+                if let (Some(var_x), Some(var_r)) = (input.var(), residual.var()) {
+                    if !var_x.is_zero() {
+                        opt_ratio = Some(var_r / var_x);
+                    }
+                }
             }
+            opt_ratio
         })
-        .collect_trusted()
+        .collect_ewm()
 }
 
-pub fn ewm_var<I, T>(
+/// How [`ewm_mean_winsorized`] orders `NaN` against the band.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum NanOrdering {
+    /// `NaN` is greater than every value and is clipped to the upper bound of the band.
+    #[default]
+    Greatest,
+    /// `NaN` is unordered and skipped like a null.
+    Skip,
+}
+
+/// Robust exponentially weighted mean of winsorized observations.
+///
+/// Each observation is clipped to `mean ± k * std` of the running average before it
+/// contributes, where `mean` and the biased `std` are the exponentially weighted
+/// moments of the clipped series so far. Clipping starts once `min_periods`
+/// observations were seen and the band has a non-zero width, so extreme outliers
+/// only move the center by a bounded amount. A `NaN` is handled according to
+/// `nan_ordering`; it is skipped while there is no band to clip it to.
+pub fn ewm_mean_winsorized<I, T>(
     xs: I,
     alpha: T,
+    k: T,
     adjust: bool,
-    bias: bool,
     min_periods: usize,
+    nan_ordering: NanOrdering,
 ) -> PrimitiveArray<T>
 where
     I: IntoIterator<Item = Option<T>>,
     I::IntoIter: TrustedLen,
     T: Float + NativeType + AddAssign,
 {
-    let one_sub_alpha = T::one() - alpha;
-    let two = T::one() + T::one();
-
-    let mut opt_mean = None;
-    let mut opt_var = None;
-    let mut non_null_cnt = 0usize;
+    let mut state = EwmVarState::new(alpha, adjust, true, min_periods);
+    xs.into_iter()
+        .map(|opt_x| {
+            let opt_x = opt_x.and_then(|x| match (state.mean(), state.var()) {
+                (Some(mean), Some(var)) if var > T::zero() => {
+                    let band = k * var.sqrt();
+                    match nan_ordering {
+                        NanOrdering::Greatest if x.is_nan() => Some(mean + band),
+                        _ if x.is_nan() => None,
+                        _ => Some(x.max(mean - band).min(mean + band)),
+                    }
+                }
+                _ => (!x.is_nan()).then_some(x),
+            });
+            state.update(opt_x)
+        })
+        .collect_ewm()
+}
 
-    let wgt = alpha;
+/// What [`ewm_zscore`] emits where the standard deviation is zero and the observation
+/// equals the mean.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum ZeroStd {
+    /// Emit a null.
+    #[default]
+    Null,
+    /// Emit `0`; the observation does not deviate from the mean.
+    Zero,
+}
 
-    let (mut wgt_sum, mut wgt_sum_sqr) = if adjust {
-        (T::zero(), T::zero())
-    } else {
-        // NOTE: we must ensure `wgt_sum` and `wgt_sum_sqr` are equal
-        // to 1 during the first iteration
-        (T::one(), (T::one() + alpha) / one_sub_alpha)
+/// Z-score `(x - mean) / std` of each observation against the [`ewm_mean`](super::ewm_mean)
+/// and [`ewm_std`], computed in a single pass.
+///
+/// With `include_current` the observation is part of the statistics it is scored
+/// against, which are those at the same position. Otherwise it is scored against the
+/// statistics as of the previous observation, so an outlier is scored before it
+/// entered them, which suits anomaly detection.
+///
+/// The z-score is null during the warm-up of `min_periods` observations and where the
+/// input is null. Where the standard deviation is zero, an observation equal to the
+/// mean is handled according to `zero_std`, while any other observation scores `±inf`:
+/// without `include_current` this flags a spike after a constant run. An undefined
+/// standard deviation (`alpha=1` without `bias`, see [`EwmVarState::var`]) gives a
+/// null.
+pub fn ewm_zscore<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    bias: bool,
+    min_periods: usize,
+    zero_std: ZeroStd,
+    include_current: bool,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    let mut state = EwmVarState::new(alpha, adjust, bias, min_periods);
+    let score = |state: &EwmVarState<T>, x: T| match (state.mean(), state.std()) {
+        (Some(mean), Some(std)) if std > T::zero() => Some((x - mean) / std),
+        (Some(mean), Some(std)) if std.is_zero() => match zero_std {
+            _ if x != mean => Some((x - mean) / std),
+            ZeroStd::Null => None,
+            ZeroStd::Zero => Some(T::zero()),
+        },
+        _ => None,
     };
 
     xs.into_iter()
         .map(|opt_x| {
-            if let Some(x) = opt_x {
-                non_null_cnt += 1;
-
-                let prev_mean = opt_mean.unwrap_or(x);
-                let prev_var = opt_var.unwrap_or_else(T::zero);
-
-                wgt_sum = one_sub_alpha * wgt_sum + wgt;
-                wgt_sum_sqr = one_sub_alpha.powf(two) * wgt_sum_sqr + wgt.powf(two);
-
-                let curr_mean = prev_mean + (x - prev_mean) * wgt / wgt_sum;
-                let curr_var = (T::one() - wgt / wgt_sum)
-                    * (prev_var + wgt / wgt_sum * (x - prev_mean).powf(two));
-
-                opt_mean = Some(curr_mean);
-                opt_var = Some(curr_var);
-            }
-            match non_null_cnt < min_periods {
-                true => None,
-                false => opt_var.map(|var| {
-                    // NOTE: the `non_null_cnt.is_one()` condition prevents a NaN
-                    // from appearing in the first entry (it prevents a zero division)
-                    let correction = if bias || non_null_cnt.is_one() {
-                        T::one()
-                    } else {
-                        T::one() - wgt_sum_sqr / wgt_sum.powf(two)
-                    };
-                    var / correction
-                }),
+            let x = opt_x?;
+            if include_current {
+                state.update(opt_x);
+                score(&state, x)
+            } else {
+                let score = score(&state, x);
+                state.update(opt_x);
+                score
             }
         })
-        .collect_trusted()
+        .collect_ewm()
+}
+
+/// Standardized residual `(x - mean) / std` of each observation, for anomaly detection.
+///
+/// This is [`ewm_zscore`] against the statistics as of the previous observation, so an
+/// outlier is scored before it entered them. The score is null during the warm-up of
+/// `min_periods` observations, where the input is null and where it equals the mean
+/// of a history without spread.
+pub fn ewm_anomaly_score<I, T>(
+    xs: I,
+    alpha: T,
+    adjust: bool,
+    bias: bool,
+    min_periods: usize,
+) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    ewm_zscore(xs, alpha, adjust, bias, min_periods, ZeroStd::Null, false)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::kernels::ewm::ewm_mean;
+    use crate::kernels::ewm::golden::uniform_noise;
 
     const XS: [Option<f64>; 7] = [
         Some(1.0),
@@ -217,4 +331,274 @@ mod test {
         ]);
         assert_eq!(polars_result, pandas_result);
     }
+
+    fn assert_close(result: PrimitiveArray<f64>, expected: &[f64]) {
+        let result = result
+            .iter()
+            .skip(1)
+            .map(|v| *v.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(result.len(), expected.len());
+        for (r, e) in result.iter().zip(expected) {
+            assert!((r - e).abs() < 1e-12 * e.abs(), "{r} != {e}");
+        }
+    }
+
+    #[test]
+    fn test_ewm_var_small_sample_correction() {
+        // With two observations the unbiased variance no longer depends on the
+        // weights: it is `(x_1 - x_2)^2 / 2`.
+        let xs = vec![Some(2.0), Some(10.0)];
+        for adjust in [true, false] {
+            assert_close(ewm_var(xs.clone(), 0.3, adjust, false, 0), &[32.0]);
+        }
+        assert_close(
+            ewm_var(xs.clone(), 0.3, true, true, 0),
+            &[15.501730103806228],
+        );
+        assert_close(ewm_var(xs, 0.3, false, true, 0), &[13.44]);
+
+        let xs = vec![Some(2.0), Some(10.0), Some(4.0)];
+        let pandas_adjusted_unbiased = [32.0, 16.018264840182646];
+        let pandas_adjusted_biased = [15.501730103806228, 10.239986655824524];
+        let pandas_unadjusted_unbiased = [32.0, 15.087248322147646];
+        let pandas_unadjusted_biased = [13.44, 9.4416];
+        assert_close(
+            ewm_var(xs.clone(), 0.3, true, false, 0),
+            &pandas_adjusted_unbiased,
+        );
+        assert_close(
+            ewm_var(xs.clone(), 0.3, true, true, 0),
+            &pandas_adjusted_biased,
+        );
+        assert_close(
+            ewm_var(xs.clone(), 0.3, false, false, 0),
+            &pandas_unadjusted_unbiased,
+        );
+        assert_close(ewm_var(xs, 0.3, false, true, 0), &pandas_unadjusted_biased);
+    }
+
+    #[test]
+    fn test_ewm_var_alpha_one() {
+        // only the newest observation has weight, regardless of `adjust`
+        let xs = vec![Some(2.0), Some(10.0), None, Some(4.0)];
+        for adjust in [true, false] {
+            let biased = ewm_var(xs.clone(), 1.0, adjust, true, 0);
+            assert_eq!(biased, PrimitiveArray::from_vec(vec![0.0; 4]));
+
+            let unbiased = ewm_var(xs.clone(), 1.0, adjust, false, 0);
+            assert_eq!(unbiased.value(0), 0.0);
+            assert!(unbiased.values()[1..].iter().all(|v| v.is_nan()));
+
+            let mut state = EwmVarState::new(1.0, adjust, false, 0);
+            assert_eq!(state.effective_n(), None);
+            for x in xs.iter().copied() {
+                state.update(x);
+                assert_eq!(state.effective_n(), Some(1.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ewm_sem_decreases() {
+        let xs = (0..30)
+            .map(|i| Some(if i % 2 == 0 { 1.0 } else { -1.0 }))
+            .collect::<Vec<_>>();
+        let sem = ewm_sem(xs.clone(), 0.1, true, false, 0);
+        let sem = sem.values().as_slice();
+        assert_eq!(sem[0], 0.0);
+        assert!(sem[1..].windows(2).all(|w| w[0] > w[1]));
+
+        // the standard error is smaller than the standard deviation of the data
+        let std = ewm_std(xs, 0.1, true, false, 0);
+        assert!(sem[1..]
+            .iter()
+            .zip(&std.values()[1..])
+            .all(|(sem, std)| sem < std));
+    }
+
+    #[test]
+    fn test_ewm_residual_var_ratio() {
+        let alpha = 0.1;
+
+        let noise = uniform_noise(0x2545_f491_4f6c_dd1d)
+            .take(20_000)
+            .map(Some)
+            .collect::<Vec<_>>();
+        let ratio = ewm_residual_var_ratio(noise, alpha);
+        let avg = ratio.values().iter().skip(100).sum::<f64>() / (ratio.len() - 100) as f64;
+
+        // for white noise the residual is (1 - alpha) * (x - previous mean), whose
+        // variance is (1 - alpha)^2 * 2 / (2 - alpha) times the input variance
+        let theory = (1.0 - alpha) * (1.0 - alpha) * 2.0 / (2.0 - alpha);
+        assert!((avg - theory).abs() < 0.05);
+
+        let trend = (0..200).map(|i| Some(i as f64)).collect::<Vec<_>>();
+        let ratio = ewm_residual_var_ratio(trend, alpha);
+        assert!(ratio.value(199) < 0.1);
+    }
+
+    #[test]
+    fn test_ewm_mean_winsorized() {
+        let mut xs = (0..50)
+            .map(|i| Some(if i % 2 == 0 { 9.0f64 } else { 11.0 }))
+            .collect::<Vec<_>>();
+        xs.push(Some(1000.0));
+        xs.push(Some(10.0));
+
+        let plain = ewm_mean(xs.clone(), 0.2, true, 2);
+        let robust = ewm_mean_winsorized(xs.clone(), 0.2, 3.0, true, 2, NanOrdering::Greatest);
+
+        // within the band the outlier-free part is untouched
+        assert!(robust
+            .values()
+            .iter()
+            .zip(plain.values().iter())
+            .take(50)
+            .all(|(a, b)| a == b));
+        // the outlier is clamped to at most 3 standard deviations (about 1) above the mean
+        assert!(plain.value(50) > 150.0);
+        assert!(robust.value(50) < 10.0 + 0.2 * 4.0);
+    }
+
+    #[test]
+    fn test_ewm_mean_winsorized_nan_ordering() {
+        let mut xs = (0..20)
+            .map(|i| Some(if i % 2 == 0 { 9.0f64 } else { 11.0 }))
+            .collect::<Vec<_>>();
+        xs[0] = Some(f64::NAN);
+        xs.push(Some(f64::NAN));
+        xs.push(Some(10.0));
+
+        let greatest = ewm_mean_winsorized(xs.clone(), 0.2, 3.0, true, 2, NanOrdering::Greatest);
+        let skip = ewm_mean_winsorized(xs.clone(), 0.2, 3.0, true, 2, NanOrdering::Skip);
+
+        // without a band yet, the leading NaN is skipped by both
+        let mut cleaned = xs.clone();
+        cleaned[0] = None;
+        cleaned[20] = None;
+        let plain = ewm_mean(cleaned.clone(), 0.2, true, 2);
+        assert_eq!(
+            skip,
+            ewm_mean_winsorized(cleaned, 0.2, 3.0, true, 2, NanOrdering::Skip)
+        );
+        assert_eq!(skip.value(20), plain.value(20));
+        assert!(greatest.values().iter().all(|v| !v.is_nan()));
+        assert_eq!(greatest.value(19), skip.value(19));
+
+        // the NaN is clipped to the upper bound of the band
+        assert!(greatest.value(20) > skip.value(20));
+        assert!(greatest.value(20) < 10.0 + 0.2 * 4.0);
+    }
+
+    #[test]
+    fn test_ewm_anomaly_score() {
+        let mut xs = (0..60)
+            .map(|i| Some(if i % 2 == 0 { 9.0f64 } else { 11.0 }))
+            .collect::<Vec<_>>();
+        xs[40] = Some(30.0);
+        xs[45] = None;
+
+        let score = ewm_anomaly_score(xs, 0.1, true, false, 5);
+        let score = score.iter().map(|v| v.copied()).collect::<Vec<_>>();
+
+        assert!(score[..5].iter().all(|v| v.is_none()));
+        assert!(score[45].is_none());
+        assert!(score[40].unwrap() > 10.0);
+        assert!(score[10..40].iter().all(|v| v.unwrap().abs() < 2.0));
+        assert!(score[55..].iter().all(|v| v.unwrap().abs() < 2.0));
+    }
+
+    #[test]
+    fn test_ewm_zscore() {
+        let xs = vec![Some(3.0f64); 10];
+        let out = ewm_zscore(xs.clone(), 0.1, true, false, 1, ZeroStd::Null, true);
+        assert_eq!(out.iter().filter(|v| v.is_some()).count(), 0);
+        let out = ewm_zscore(xs, 0.1, true, false, 1, ZeroStd::Zero, true);
+        assert_eq!(out, PrimitiveArray::from_vec(vec![0.0; 10]));
+
+        let mut xs = (0..40)
+            .map(|i| Some(if i % 2 == 0 { 9.0f64 } else { 11.0 }))
+            .collect::<Vec<_>>();
+        xs[30] = Some(30.0);
+        xs[35] = None;
+        let out = ewm_zscore(xs.clone(), 0.1, true, false, 5, ZeroStd::Null, true);
+        let out = out.iter().map(|v| v.copied()).collect::<Vec<_>>();
+        assert!(out[..4].iter().all(|v| v.is_none()));
+        assert!(out[35].is_none());
+        assert!(out[30].unwrap() > 2.5);
+        assert!(out[10..30].iter().all(|v| v.unwrap().abs() < 1.5));
+
+        // consistent with the separate kernels
+        let mean = ewm_mean(xs.clone(), 0.1, true, 5);
+        let std = ewm_std(xs, 0.1, true, false, 5);
+        let expected = (30.0 - mean.value(30)) / std.value(30);
+        assert!((out[30].unwrap() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_ewm_zscore_spike_after_constant() {
+        let mut xs = vec![Some(3.0f64); 10];
+        xs.push(Some(5.0));
+        xs.push(Some(1.0));
+        for zero_std in [ZeroStd::Null, ZeroStd::Zero] {
+            let out = ewm_zscore(xs.clone(), 0.1, true, false, 1, zero_std, false);
+            // the spike is scored against the constant run before it
+            assert_eq!(out.value(10), f64::INFINITY);
+            assert!(out.value(11) < -1.0);
+            let mut constant = out.iter().take(10).skip(1).map(|v| v.copied());
+            match zero_std {
+                ZeroStd::Null => assert!(constant.all(|v| v.is_none())),
+                ZeroStd::Zero => assert!(constant.all(|v| v == Some(0.0))),
+            }
+        }
+        let xs = vec![Some(3.0f64), Some(3.0), Some(-1.0)];
+        let out = ewm_zscore(xs, 0.1, true, false, 1, ZeroStd::Zero, false);
+        assert_eq!(out.value(2), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_ewm_zscore_alpha_one() {
+        // the mean is the newest observation and the data has no spread around it
+        let xs = vec![Some(1.0f64), Some(5.0), None, Some(2.0)];
+        for adjust in [true, false] {
+            let biased = ewm_zscore(xs.clone(), 1.0, adjust, true, 0, ZeroStd::Zero, true);
+            assert_eq!(
+                biased,
+                PrimitiveArray::from([Some(0.0), Some(0.0), None, Some(0.0)])
+            );
+
+            // without `bias` the standard deviation is undefined after the first value
+            let unbiased = ewm_zscore(xs.clone(), 1.0, adjust, false, 0, ZeroStd::Zero, true);
+            assert_eq!(
+                unbiased,
+                PrimitiveArray::from([Some(0.0), None, None, None])
+            );
+
+            for include_current in [true, false] {
+                for bias in [true, false] {
+                    assert_eq!(
+                        ewm_zscore(
+                            xs.clone(),
+                            1.0,
+                            adjust,
+                            bias,
+                            0,
+                            ZeroStd::Null,
+                            include_current
+                        ),
+                        ewm_zscore(
+                            xs.clone(),
+                            1.0,
+                            !adjust,
+                            bias,
+                            0,
+                            ZeroStd::Null,
+                            include_current
+                        ),
+                    );
+                }
+            }
+        }
+    }
 }
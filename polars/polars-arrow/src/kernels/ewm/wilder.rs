@@ -0,0 +1,81 @@
+use std::ops::AddAssign;
+
+use arrow::array::PrimitiveArray;
+use arrow::types::NativeType;
+use num::{Float, NumCast};
+
+use super::collect::CollectEwm;
+use crate::trusted_len::TrustedLen;
+
+/// Wilder's smoothing as used in the RSI and ATR indicators.
+///
+/// The first output is the simple average of the first `n` non-null observations,
+/// after which the recurrence `s = (s * (n - 1) + x) / n` is applied, i.e. an
+/// exponentially weighted mean with `alpha = 1 / n`. Positions before the seed are
+/// null and nulls carry the previous value forward.
+///
+/// # Panics
+/// Panics if `n == 0`.
+pub fn wilder_smooth<I, T>(xs: I, n: usize) -> PrimitiveArray<T>
+where
+    I: IntoIterator<Item = Option<T>>,
+    I::IntoIter: TrustedLen,
+    T: Float + NativeType + AddAssign,
+{
+    assert!(n > 0);
+    let n_t: T = NumCast::from(n).unwrap();
+    let n_sub_one = n_t - T::one();
+
+    let mut non_null_cnt = 0usize;
+    let mut seed_sum = T::zero();
+    let mut opt_smoothed = None;
+
+    xs.into_iter()
+        .map(|opt_x| {
+            if let Some(x) = opt_x {
+                non_null_cnt += 1;
+                match opt_smoothed {
+                    Some(prev) => opt_smoothed = Some((prev * n_sub_one + x) / n_t),
+                    None => {
+                        seed_sum += x;
+                        if non_null_cnt == n {
+                            opt_smoothed = Some(seed_sum / n_t);
+                        }
+                    }
+                }
+            }
+            opt_smoothed
+        })
+        .collect_ewm()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_wilder_smooth() {
+        let xs = vec![Some(1.0f64), Some(3.0), Some(5.0), None, Some(7.0)];
+        let result = wilder_smooth(xs, 2);
+        let expected = PrimitiveArray::from([None, Some(2.0), Some(3.5), Some(3.5), Some(5.25)]);
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_wilder_smooth_rsi_gains() {
+        // average gain of the 14-period RSI on a series of closing price changes
+        let gains = [
+            0.0, 0.06, 0.0, 0.72, 0.5, 0.27, 0.33, 0.42, 0.24, 0.0, 0.14, 0.0, 0.67, 0.0, 0.0, 0.28,
+        ];
+        let result = wilder_smooth(gains.iter().copied().map(Some), 14);
+
+        let seed = gains[..14].iter().sum::<f64>() / 14.0;
+        let next = (seed * 13.0 + gains[14]) / 14.0;
+        let last = (next * 13.0 + gains[15]) / 14.0;
+
+        assert!(result.iter().take(13).all(|v| v.is_none()));
+        assert!((result.value(13) - seed).abs() < 1e-12);
+        assert!((result.value(14) - next).abs() < 1e-12);
+        assert!((result.value(15) - last).abs() < 1e-12);
+    }
+}
@@ -71,3 +71,72 @@ where
         }
     }
 }
+
+/// Rolling geometric mean `exp(mean(ln(window)))`, computed from a running sum of logs.
+///
+/// Windows containing a non-positive value have no geometric mean and are null.
+pub fn rolling_geometric_mean<T>(
+    values: &[T],
+    window_size: usize,
+    min_periods: usize,
+    center: bool,
+) -> ArrayRef
+where
+    T: NativeType + Float + std::iter::Sum<T> + SubAssign + AddAssign + IsFloat,
+{
+    let logs = values
+        .iter()
+        .map(|v| if *v > T::zero() { v.ln() } else { T::nan() })
+        .collect::<Vec<_>>();
+    let log_means = rolling_mean(&logs, window_size, min_periods, center, None);
+    let log_means = log_means
+        .as_any()
+        .downcast_ref::<PrimitiveArray<T>>()
+        .unwrap();
+
+    let out = log_means
+        .values()
+        .iter()
+        .map(|v| v.exp())
+        .collect_trusted::<Vec<_>>();
+    let validity = log_means
+        .iter()
+        .map(|opt_v| matches!(opt_v, Some(v) if !v.is_nan()))
+        .collect::<MutableBitmap>();
+    Box::new(PrimitiveArray::new(
+        T::PRIMITIVE.into(),
+        out.into(),
+        Some(validity.into()),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rolling_geometric_mean() {
+        let values = &[1.0f64, 2.0, 4.0, 8.0, 0.5, -1.0, 3.0];
+        let out = rolling_geometric_mean(values, 3, 3, false);
+        let out = out.as_any().downcast_ref::<PrimitiveArray<f64>>().unwrap();
+        let out = out.into_iter().map(|v| v.copied()).collect::<Vec<_>>();
+
+        let expected = (0..values.len())
+            .map(|i| {
+                let window = &values[i.saturating_sub(2)..=i];
+                (i >= 2 && window.iter().all(|v| *v > 0.0))
+                    .then(|| window.iter().product::<f64>().powf(1.0 / 3.0))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(out.len(), expected.len());
+        for (a, b) in out.iter().zip(expected) {
+            match (a, b) {
+                (Some(a), Some(b)) => assert!((a - b).abs() < 1e-12),
+                (a, b) => assert_eq!(*a, b),
+            }
+        }
+        // the windows containing -1.0 are null
+        assert_eq!(out.iter().filter(|v| v.is_none()).count(), 4);
+    }
+}
@@ -288,6 +288,99 @@ where
     ))
 }
 
+/// Rolling mean of each window after dropping its `trim_k` smallest and `trim_k` largest
+/// values.
+///
+/// Windows that are too small to drop `2 * trim_k` values (e.g. at the edges) are
+/// trimmed as far as possible while keeping at least one value.
+pub fn rolling_trimmed_mean<T>(
+    values: &[T],
+    window_size: usize,
+    min_periods: usize,
+    center: bool,
+    trim_k: usize,
+) -> ArrayRef
+where
+    T: NativeType + Float + IsFloat + PartialOrd,
+{
+    if center {
+        rolling_apply_trimmed_mean(values, window_size, min_periods, trim_k, det_offsets_center)
+    } else {
+        rolling_apply_trimmed_mean(values, window_size, min_periods, trim_k, det_offsets)
+    }
+}
+
+fn rolling_apply_trimmed_mean<T, Fo>(
+    values: &[T],
+    window_size: usize,
+    min_periods: usize,
+    trim_k: usize,
+    det_offsets_fn: Fo,
+) -> ArrayRef
+where
+    Fo: Fn(Idx, WindowSize, Len) -> (Start, End),
+    T: NativeType + Float + IsFloat + PartialOrd,
+{
+    let len = values.len();
+    let (start, end) = det_offsets_fn(0, window_size, len);
+    let mut sorted_window = SortedBuf::new(values, start, end);
+
+    let out = (0..len)
+        .map(|idx| {
+            let (start, end) = det_offsets_fn(idx, window_size, len);
+
+            // Safety:
+            // we are in bounds
+            let window = unsafe { sorted_window.update(start, end) };
+            let trim = std::cmp::min(trim_k, window.len().saturating_sub(1) / 2);
+            let kept = &window[trim..window.len() - trim];
+            kept.iter().fold(T::zero(), |acc, v| acc + *v) / NumCast::from(kept.len()).unwrap()
+        })
+        .collect_trusted::<Vec<T>>();
+
+    let validity = create_validity(min_periods, len, window_size, det_offsets_fn);
+    Box::new(PrimitiveArray::new(
+        T::PRIMITIVE.into(),
+        out.into(),
+        validity.map(|b| b.into()),
+    ))
+}
+
+/// Fraction of the values in each trailing window that are smaller than the last value
+/// of the window, i.e. where the current value ranks within its recent history.
+///
+/// The current value counts towards the window, so a new maximum of a full window
+/// ranks `(window_size - 1) / window_size`. `NaN` is larger than every other value.
+pub fn rolling_percentile_rank<T>(values: &[T], window_size: usize, min_periods: usize) -> ArrayRef
+where
+    T: NativeType + Float + IsFloat + PartialOrd,
+{
+    let len = values.len();
+    let (start, end) = det_offsets(0, window_size, len);
+    let mut sorted_window = SortedBuf::new(values, start, end);
+
+    let out = (0..len)
+        .map(|idx| {
+            let (start, end) = det_offsets(idx, window_size, len);
+
+            // Safety:
+            // we are in bounds
+            let window = unsafe { sorted_window.update(start, end) };
+            let current = values[idx];
+            let below =
+                window.partition_point(|v| compare_fn_nan_max(v, &current) == Ordering::Less);
+            T::from(below).unwrap() / T::from(window.len()).unwrap()
+        })
+        .collect_trusted::<Vec<T>>();
+
+    let validity = create_validity(min_periods, len, window_size, det_offsets);
+    Box::new(PrimitiveArray::new(
+        T::PRIMITIVE.into(),
+        out.into(),
+        validity.map(|b| b.into()),
+    ))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -393,4 +486,54 @@ mod test {
             assert_eq!(out1, out2);
         }
     }
+
+    #[test]
+    fn test_rolling_trimmed_mean() {
+        let values = &[1.0f64, 100.0, 2.0, 3.0, -50.0, 4.0, 5.0];
+
+        let out = rolling_trimmed_mean(values, 5, 5, false, 1);
+        let out = out.as_any().downcast_ref::<PrimitiveArray<f64>>().unwrap();
+        let out = out.into_iter().map(|v| v.copied()).collect::<Vec<_>>();
+        // the outliers are dropped from every full window that contains them
+        assert_eq!(
+            out,
+            &[None, None, None, None, Some(2.0), Some(3.0), Some(3.0)]
+        );
+
+        let out = rolling_trimmed_mean(values, 5, 1, false, 1);
+        let out = out.as_any().downcast_ref::<PrimitiveArray<f64>>().unwrap();
+        let out = out.into_iter().map(|v| v.copied()).collect::<Vec<_>>();
+        assert_eq!(&out[..3], &[Some(1.0), Some(50.5), Some(2.0)]);
+
+        let out = rolling_trimmed_mean(values, 3, 1, false, 0);
+        let out = out.as_any().downcast_ref::<PrimitiveArray<f64>>().unwrap();
+        let expected = rolling_mean(values, 3, 1, false, None);
+        let expected = expected
+            .as_any()
+            .downcast_ref::<PrimitiveArray<f64>>()
+            .unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn test_rolling_percentile_rank() {
+        let values = &[3.0f64, 1.0, 2.0, 2.0, 10.0, 0.5, 4.0];
+
+        let out = rolling_percentile_rank(values, 4, 2);
+        let out = out.as_any().downcast_ref::<PrimitiveArray<f64>>().unwrap();
+        let out = out.into_iter().map(|v| v.copied()).collect::<Vec<_>>();
+        assert_eq!(
+            out,
+            &[
+                None,
+                Some(0.0),
+                Some(1.0 / 3.0),
+                Some(0.25),
+                // a new extreme ranks above every other value in the window
+                Some(0.75),
+                Some(0.0),
+                Some(0.5)
+            ]
+        );
+    }
 }
@@ -1,6 +1,5 @@
-use arrow::bitmap::utils::{count_zeros, ZipValidityIter};
-use nulls;
-use nulls::{rolling_apply_agg_window, RollingAggWindowNulls};
+use arrow::bitmap::utils::ZipValidityIter;
+use nulls::{self, rolling_apply_agg_window, RollingAggWindowNulls};
 
 use super::*;
 
@@ -41,9 +40,14 @@ pub struct SortedMinMax<'a, T: NativeType> {
 }
 
 impl<'a, T: NativeType> SortedMinMax<'a, T> {
-    fn count_nulls(&self, start: usize, end: usize) -> usize {
+    fn scan(&self, start: usize, end: usize) -> BitmapScanner<'a> {
         let (bytes, offset, _) = self.validity.as_slice();
-        count_zeros(bytes, offset + start, end - start)
+        BitmapScanner::new(bytes, offset + start, end - start)
+    }
+
+    fn count_nulls(&self, start: usize, end: usize) -> usize {
+        let len = end - start;
+        len - self.scan(start, end).count_ones_before(len)
     }
 }
 
@@ -69,15 +73,9 @@ impl<'a, T: NativeType> RollingAggWindowNulls<'a, T> for SortedMinMax<'a, T> {
         self.last_end = end;
 
         // return first non null
-        for idx in start..end {
-            let valid = self.validity.get_bit_unchecked(idx);
-
-            if valid {
-                return Some(*self.slice.get_unchecked(idx));
-            }
-        }
-
-        None
+        self.scan(start, end)
+            .next_set_bit(0)
+            .map(|idx| *self.slice.get_unchecked(start + idx))
     }
 
     fn is_valid(&self, min_periods: usize) -> bool {
@@ -432,3 +430,97 @@ where
         )
     }
 }
+
+/// Offset within each window of its extreme, the value that `compare` orders greatest.
+/// Ties resolve to the first occurrence.
+fn rolling_arg_extreme<T>(
+    arr: &PrimitiveArray<T>,
+    window_size: usize,
+    min_periods: usize,
+    center: bool,
+    compare: impl Fn(&T, &T) -> Ordering,
+) -> PrimitiveArray<IdxSize>
+where
+    T: NativeType,
+{
+    use arrow::array::Array;
+
+    let det_offsets_fn = if center {
+        det_offsets_center
+    } else {
+        det_offsets
+    };
+    let values = arr.values().as_slice();
+    let len = values.len();
+
+    // indices of the candidates for the extreme of the current and later windows,
+    // their values are decreasing according to `compare`
+    let mut candidates = std::collections::VecDeque::with_capacity(window_size);
+    let (mut last_start, mut last_end) = (0, 0);
+    let mut valid_count = 0;
+
+    (0..len)
+        .map(|idx| {
+            let (start, end) = det_offsets_fn(idx, window_size, len);
+            for i in last_end..end {
+                if arr.is_valid(i) {
+                    valid_count += 1;
+                    while let Some(&back) = candidates.back() {
+                        if compare(&values[back], &values[i]) == Ordering::Less {
+                            candidates.pop_back();
+                        } else {
+                            break;
+                        }
+                    }
+                    candidates.push_back(i);
+                }
+            }
+            valid_count -= (last_start..start).filter(|&i| arr.is_valid(i)).count();
+            while let Some(&front) = candidates.front() {
+                if front >= start {
+                    break;
+                }
+                candidates.pop_front();
+            }
+            last_start = start;
+            last_end = end;
+
+            if valid_count < std::cmp::max(min_periods, 1) {
+                return None;
+            }
+            candidates.front().map(|&i| (i - start) as IdxSize)
+        })
+        .collect()
+}
+
+/// Offset (from `0` to `window_size - 1`) of the maximum within each window. Nulls are
+/// skipped, ties resolve to the first occurrence and `NaN` is the largest value.
+/// Windows with fewer than `min_periods` valid values are null.
+pub fn rolling_argmax<T>(
+    arr: &PrimitiveArray<T>,
+    window_size: usize,
+    min_periods: usize,
+    center: bool,
+) -> PrimitiveArray<IdxSize>
+where
+    T: NativeType + PartialOrd + IsFloat,
+{
+    rolling_arg_extreme(arr, window_size, min_periods, center, compare_fn_nan_max)
+}
+
+/// Offset (from `0` to `window_size - 1`) of the minimum within each window. Nulls are
+/// skipped, ties resolve to the first occurrence and `NaN` is the smallest value.
+/// Windows with fewer than `min_periods` valid values are null.
+pub fn rolling_argmin<T>(
+    arr: &PrimitiveArray<T>,
+    window_size: usize,
+    min_periods: usize,
+    center: bool,
+) -> PrimitiveArray<IdxSize>
+where
+    T: NativeType + PartialOrd + IsFloat,
+{
+    rolling_arg_extreme(arr, window_size, min_periods, center, |a, b| {
+        compare_fn_nan_min(b, a)
+    })
+}
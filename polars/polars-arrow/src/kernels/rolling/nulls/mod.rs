@@ -246,4 +246,36 @@ mod test {
             &[3, 10, 10, 10, 10, 10, 9, 8, 7, 6, 5, 4, 3]
         );
     }
+
+    #[test]
+    fn test_rolling_argmin_argmax() {
+        let arr = PrimitiveArray::from([
+            Some(1.0f64),
+            Some(3.0),
+            None,
+            Some(2.0),
+            Some(5.0),
+            Some(4.0),
+            Some(0.0),
+        ]);
+
+        // the maximum 5 enters at the end of the window and moves to its start
+        let out = rolling_argmax(&arr, 3, 1, false);
+        let expected = [0, 1, 1, 0, 2, 1, 0].map(|i: IdxSize| Some(i));
+        assert_eq!(out, PrimitiveArray::from(expected));
+
+        let out = rolling_argmin(&arr, 3, 1, false);
+        let expected = [0, 0, 0, 2, 1, 0, 2].map(|i: IdxSize| Some(i));
+        assert_eq!(out, PrimitiveArray::from(expected));
+
+        let out = rolling_argmax(&arr, 3, 2, false);
+        assert_eq!(
+            out,
+            PrimitiveArray::from([None, Some(1), Some(1), Some(0), Some(2), Some(1), Some(0)])
+        );
+
+        let out = rolling_argmax(&arr, 1, 1, false);
+        assert_eq!(out.null_count(), 1);
+        assert!(!out.is_valid(2));
+    }
 }
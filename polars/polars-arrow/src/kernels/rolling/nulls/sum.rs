@@ -16,19 +16,20 @@ impl<'a, T: NativeType + IsFloat + Add<Output = T> + Sub<Output = T>> SumWindow<
     // compute sum from the entire window
     unsafe fn compute_sum_and_null_count(&mut self, start: usize, end: usize) -> Option<T> {
         let mut sum = None;
-        let mut idx = start;
         self.null_count = 0;
-        for value in &self.slice[start..end] {
-            let valid = self.validity.get_bit_unchecked(idx);
-            if valid {
-                match sum {
-                    None => sum = Some(*value),
-                    Some(current) => sum = Some(*value + current),
+        let (bytes, offset, _) = self.validity.as_slice();
+        for run in BitmapScanner::new(bytes, offset + start, end - start) {
+            if run.is_set {
+                let run_start = start + run.start;
+                for value in &self.slice[run_start..run_start + run.len] {
+                    match sum {
+                        None => sum = Some(*value),
+                        Some(current) => sum = Some(*value + current),
+                    }
                 }
             } else {
-                self.null_count += 1;
+                self.null_count += run.len;
             }
-            idx += 1;
         }
         self.sum = sum;
         sum
@@ -3,6 +3,7 @@ use arrow::array::{BinaryArray, ListArray, Utf8Array};
 pub use crate::array::default_arrays::*;
 pub use crate::array::*;
 pub use crate::bitmap::mutable::MutableBitmapExtension;
+pub use crate::bitmap::scanner::{BitRun, BitmapScanner};
 pub use crate::data_types::*;
 pub use crate::index::*;
 pub use crate::kernels::rolling::no_nulls::QuantileInterpolOptions;
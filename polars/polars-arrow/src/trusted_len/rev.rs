@@ -1,5 +1,30 @@
+use arrow::array::PrimitiveArray;
+use arrow::bitmap::MutableBitmap;
+use arrow::types::NativeType;
+
+use crate::array::default_arrays::FromData;
 use crate::trusted_len::TrustedLen;
 
 pub trait FromIteratorReversed<T>: Sized {
     fn from_trusted_len_iter_rev<I: TrustedLen<Item = T>>(iter: I) -> Self;
 }
+
+impl<T: NativeType> FromIteratorReversed<Option<T>> for PrimitiveArray<T> {
+    fn from_trusted_len_iter_rev<I: TrustedLen<Item = Option<T>>>(iter: I) -> Self {
+        let size = iter.size_hint().1.unwrap();
+
+        let mut vals = vec![T::default(); size];
+        let mut validity = MutableBitmap::with_capacity(size);
+        validity.extend_constant(size, true);
+
+        let mut offset = size;
+        iter.for_each(|opt_item| {
+            offset -= 1;
+            match opt_item {
+                Some(item) => vals[offset] = item,
+                None => validity.set(offset, false),
+            }
+        });
+        PrimitiveArray::from_data_default(vals.into(), Some(validity.into()))
+    }
+}
@@ -1,44 +1,144 @@
 use std::convert::TryFrom;
 
+use num::{Float, NumCast};
 pub use polars_arrow::kernels::ewm::EWMOptions;
-use polars_arrow::kernels::ewm::{ewm_mean, ewm_std, ewm_var};
+use polars_arrow::kernels::ewm::{
+    ewm_mean_chunks, ewm_std, ewm_var, validate_alpha, EwmChunkedScan,
+};
+use rayon::prelude::*;
 
 use crate::prelude::*;
+use crate::series::IsSorted;
+use crate::POOL;
 
-impl Series {
+fn check_alpha(options: &EWMOptions) -> PolarsResult<()> {
+    validate_alpha(options.alpha).map_err(|err| PolarsError::ComputeError(err.to_string().into()))
+}
+
+impl<T> ChunkedArray<T>
+where
+    T: PolarsFloatType,
+    T::Native: Float,
+{
+    /// Exponentially weighted mean of the values.
+    ///
+    /// The chunks are read in place, so this composes with other `ChunkedArray` kernels,
+    /// e.g. smoothing the output of a rolling aggregation, without a cast or an
+    /// intermediate `Series`. The chunks are not concatenated either.
     pub fn ewm_mean(&self, options: EWMOptions) -> PolarsResult<Self> {
-        if options.alpha <= 0. || options.alpha > 1. {
+        check_alpha(&options)?;
+        let chunks = self.downcast_iter().collect::<Vec<_>>();
+        let result = ewm_mean_chunks(
+            &chunks,
+            NumCast::from(options.alpha).unwrap(),
+            options.adjust,
+            options.min_periods,
+        );
+        // Safety: the kernel returns an array of `T::Native`
+        let out = unsafe { ChunkedArray::from_chunks(self.name(), vec![Box::new(result)]) };
+        self.finish_ewm(out, &options)
+    }
+
+    /// [`ChunkedArray::ewm_mean`] computed in parallel, in chunks of `chunk_size` values.
+    ///
+    /// The output does not depend on `chunk_size`, which only trades scheduling
+    /// overhead against load balancing. See
+    /// [`EWM_DEFAULT_CHUNK_SIZE`](polars_arrow::kernels::ewm::EWM_DEFAULT_CHUNK_SIZE)
+    /// for a default.
+    pub fn ewm_mean_par(&self, options: EWMOptions, chunk_size: usize) -> PolarsResult<Self> {
+        check_alpha(&options)?;
+        if chunk_size == 0 {
             return Err(PolarsError::ComputeError(
-                "alpha must satisfy: 0 < alpha <= 1".into(),
+                "chunk_size must be positive".into(),
             ));
+        }
+        let xs = self.into_iter().collect::<Vec<_>>();
+        let mut scan = EwmChunkedScan::new(
+            &xs,
+            NumCast::from(options.alpha).unwrap(),
+            options.adjust,
+            options.min_periods,
+            chunk_size,
+        );
+
+        let out = POOL.install(|| {
+            let summaries = (0..scan.n_blocks())
+                .into_par_iter()
+                .map(|b| scan.block_summary(b))
+                .collect::<Vec<_>>();
+            scan.seed(&summaries);
+
+            let chunks = scan.chunks().collect::<Vec<_>>();
+            chunks
+                .into_par_iter()
+                .map(|chunk| scan.compute_chunk(chunk))
+                .collect::<Vec<_>>()
+        });
+        let out = out.into_iter().flatten().collect::<ChunkedArray<T>>();
+        self.finish_ewm(out, &options)
+    }
+
+    /// Apply the options that act on the output, keep the name of the input and clear
+    /// the sorted flag: smoothing a sorted column does not keep it sorted in general.
+    fn finish_ewm(&self, out: Self, options: &EWMOptions) -> PolarsResult<Self> {
+        let mut out = if options.mask_output_to_input_validity && self.null_count() > 0 {
+            out.zip_with(
+                &self.is_not_null(),
+                &ChunkedArray::full_null(self.name(), self.len()),
+            )?
+        } else {
+            out
+        };
+        out.rename(self.name());
+        out.set_sorted_flag(IsSorted::Not);
+        Ok(out)
+    }
+}
+
+impl Series {
+    /// See [`ChunkedArray::finish_ewm`].
+    fn finish_ewm(&self, out: Series, options: &EWMOptions) -> PolarsResult<Series> {
+        let mut out = if options.mask_output_to_input_validity && self.null_count() > 0 {
+            let nulls = Series::full_null(out.name(), out.len(), out.dtype());
+            out.zip_with(&self.is_not_null(), &nulls)?
+        } else {
+            out
         };
+        out.rename(self.name());
+        out.set_sorted_flag(IsSorted::Not);
+        Ok(out)
+    }
+
+    /// Error on dtypes that have no numeric interpretation, instead of casting them.
+    fn check_ewm_dtype(&self, op: &str) -> PolarsResult<()> {
         match self.dtype() {
-            DataType::Float32 => {
-                let xs = self.f32().unwrap();
-                let result = ewm_mean(
-                    xs,
-                    options.alpha as f32,
-                    options.adjust,
-                    options.min_periods,
-                );
-                Series::try_from((self.name(), Box::new(result) as ArrayRef))
-            }
-            DataType::Float64 => {
-                let xs = self.f64().unwrap();
-                let result = ewm_mean(xs, options.alpha, options.adjust, options.min_periods);
-                Series::try_from((self.name(), Box::new(result) as ArrayRef))
-            }
+            DataType::Boolean
+            | DataType::Date
+            | DataType::Datetime(_, _)
+            | DataType::Duration(_)
+            | DataType::Null => Ok(()),
+            dtype if dtype.is_numeric() => Ok(()),
+            dtype => Err(PolarsError::InvalidOperation(
+                format!("{op} is not supported for dtype {dtype}").into(),
+            )),
+        }
+    }
+
+    pub fn ewm_mean(&self, options: EWMOptions) -> PolarsResult<Self> {
+        self.check_ewm_dtype("ewm_mean")?;
+        match self.dtype() {
+            // arrow `Float16` data is promoted to `Float32` when the `Series` is created,
+            // so it is smoothed and returned as `Float32`
+            DataType::Float32 => Ok(self.f32().unwrap().ewm_mean(options)?.into_series()),
+            DataType::Float64 => Ok(self.f64().unwrap().ewm_mean(options)?.into_series()),
             _ => self.cast(&DataType::Float64)?.ewm_mean(options),
         }
     }
 
     pub fn ewm_std(&self, options: EWMOptions) -> PolarsResult<Self> {
-        if options.alpha <= 0. || options.alpha > 1. {
-            return Err(PolarsError::ComputeError(
-                "alpha must satisfy: 0 < alpha <= 1".into(),
-            ));
-        };
-        match self.dtype() {
+        check_alpha(&options)?;
+        self.check_ewm_dtype("ewm_std")?;
+        let out = match self.dtype() {
             DataType::Float32 => {
                 let xs = self.f32().unwrap();
                 let result = ewm_std(
@@ -61,17 +161,15 @@ impl Series {
                 );
                 Series::try_from((self.name(), Box::new(result) as ArrayRef))
             }
-            _ => self.cast(&DataType::Float64)?.ewm_std(options),
-        }
+            _ => return self.cast(&DataType::Float64)?.ewm_std(options),
+        };
+        self.finish_ewm(out?, &options)
     }
 
     pub fn ewm_var(&self, options: EWMOptions) -> PolarsResult<Self> {
-        if options.alpha <= 0. || options.alpha > 1. {
-            return Err(PolarsError::ComputeError(
-                "alpha must satisfy: 0 < alpha <= 1".into(),
-            ));
-        };
-        match self.dtype() {
+        check_alpha(&options)?;
+        self.check_ewm_dtype("ewm_var")?;
+        let out = match self.dtype() {
             DataType::Float32 => {
                 let xs = self.f32().unwrap();
                 let result = ewm_var(
@@ -94,7 +192,246 @@ impl Series {
                 );
                 Series::try_from((self.name(), Box::new(result) as ArrayRef))
             }
-            _ => self.cast(&DataType::Float64)?.ewm_var(options),
+            _ => return self.cast(&DataType::Float64)?.ewm_var(options),
+        };
+        self.finish_ewm(out?, &options)
+    }
+
+    /// Rank the whole series and smooth the ranks with an exponentially weighted mean.
+    ///
+    /// Ranking needs the complete series, so this can not be computed in a streaming fashion.
+    #[cfg(feature = "rank")]
+    pub fn ewm_rank(&self, options: EWMOptions, rank_options: RankOptions) -> PolarsResult<Self> {
+        self.rank(rank_options).ewm_mean(options)
+    }
+}
+
+impl DataFrame {
+    /// Apply [`Series::ewm_mean`] with shared `options` to the given columns in parallel.
+    ///
+    /// The selected columns are replaced by their smoothed values, all other columns
+    /// are kept as is. Selecting a column whose dtype [`Series::ewm_mean`] does not
+    /// support is an error.
+    pub fn ewm_mean(&self, columns: &[&str], options: EWMOptions) -> PolarsResult<DataFrame> {
+        let idx = columns
+            .iter()
+            .map(|name| {
+                let idx = self.try_find_idx_by_name(name)?;
+                self.columns[idx].check_ewm_dtype("ewm_mean")?;
+                Ok(idx)
+            })
+            .collect::<PolarsResult<Vec<_>>>()?;
+
+        let smoothed = POOL.install(|| {
+            idx.par_iter()
+                .map(|&i| self.columns[i].ewm_mean(options))
+                .collect::<PolarsResult<Vec<_>>>()
+        })?;
+
+        let mut new_columns = self.columns.clone();
+        for (i, s) in idx.into_iter().zip(smoothed) {
+            new_columns[i] = s;
+        }
+        Ok(DataFrame::new_no_checks(new_columns))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use polars_arrow::kernels::ewm::ewm_mean_chunked;
+
+    use super::*;
+
+    #[test]
+    fn test_ewm_mask_output_to_input_validity() -> PolarsResult<()> {
+        let s = Series::new("a", &[Some(1.0), None, Some(3.0), None, None, Some(2.0)]);
+
+        let out = s.ewm_mean(EWMOptions::default())?;
+        assert_eq!(out.null_count(), 0);
+
+        let options = EWMOptions::default().and_mask_output_to_input_validity(true);
+        for out in [
+            s.ewm_mean(options)?,
+            s.ewm_std(options)?,
+            s.ewm_var(options)?,
+        ] {
+            assert!(out
+                .is_null()
+                .into_series()
+                .series_equal(&s.is_null().into_series()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_df_ewm_mean() -> PolarsResult<()> {
+        let df = df![
+            "a" => [1.0, 2.0, 3.0],
+            "b" => [4, 2, 8],
+            "c" => ["x", "y", "z"]
+        ]?;
+        let options = EWMOptions::default();
+
+        let out = df.ewm_mean(&["a", "b"], options)?;
+        assert_eq!(out.get_column_names(), &["a", "b", "c"]);
+        assert!(out
+            .column("a")?
+            .series_equal(&df.column("a")?.ewm_mean(options)?));
+        assert!(out
+            .column("b")?
+            .series_equal(&df.column("b")?.ewm_mean(options)?));
+        assert!(out.column("c")?.series_equal(df.column("c")?));
+
+        let out = df.ewm_mean(&["b"], options)?;
+        assert!(out.column("a")?.series_equal(df.column("a")?));
+
+        let err = df.ewm_mean(&["a", "c"], options).unwrap_err();
+        let expected = df.column("c")?.ewm_mean(options).unwrap_err();
+        assert!(matches!(err, PolarsError::InvalidOperation(_)));
+        assert_eq!(err.to_string(), expected.to_string());
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "rank")]
+    fn test_ewm_rank_monotonic() -> PolarsResult<()> {
+        let s = Series::new("a", &[0.3, 1.5, 2.0, 7.1, 9.9, 12.0]);
+        let out = s.ewm_rank(
+            EWMOptions::default(),
+            RankOptions {
+                method: RankMethod::Average,
+                descending: false,
+            },
+        )?;
+        let out = out.cast(&DataType::Float64)?;
+        let out = out.f64()?.into_no_null_iter().collect::<Vec<_>>();
+        assert!(out.windows(2).all(|w| w[0] < w[1]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ewm_mean_after_rolling_mean() -> PolarsResult<()> {
+        use polars_arrow::kernels::rolling::no_nulls::rolling_mean;
+
+        let ca = Float64Chunked::new("a", &[1.0, 4.0, 2.0, 8.0, 5.0, 7.0]);
+        let rolled = rolling_mean(ca.cont_slice()?, 3, 1, false, None);
+        let rolled = unsafe { Float64Chunked::from_chunks("a", vec![rolled]) };
+
+        let options = EWMOptions::default().and_adjust(false);
+        let out = rolled.ewm_mean(options)?;
+        assert_eq!(out.chunks().len(), 1);
+        assert!(out
+            .into_series()
+            .series_equal(&rolled.into_series().ewm_mean(options)?));
+
+        let options = EWMOptions {
+            alpha: 0.0,
+            ..Default::default()
+        };
+        assert!(ca.ewm_mean(options).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ewm_mean_par_chunk_sizes() -> PolarsResult<()> {
+        let ca = (0..10_000)
+            .map(|i| (i % 5 != 0).then_some((i % 13) as f64))
+            .collect::<Float64Chunked>();
+        let options = EWMOptions::default().and_span(199);
+
+        let out = ca.ewm_mean_par(options, 1 << 12)?;
+        for chunk_size in [1, 1000, 3000, 1 << 20] {
+            let other = ca.ewm_mean_par(options, chunk_size)?;
+            assert!(out
+                .clone()
+                .into_series()
+                .series_equal_missing(&other.into_series()));
+        }
+        // bit-identical to the single-threaded scan
+        let xs = ca.into_iter().collect::<Vec<_>>();
+        let serial = ewm_mean_chunked(&xs, options.alpha, options.adjust, options.min_periods, 1);
+        assert!(out
+            .into_iter()
+            .zip(serial.iter())
+            .all(|(a, b)| a.map(f64::to_bits) == b.map(|b| b.to_bits())));
+        assert!(ca.ewm_mean_par(options, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ewm_errors() {
+        let s = Series::new("a", &[1.0, 2.0, 3.0]);
+        for alpha in [0.0, -0.5, 1.5, f64::NAN] {
+            let options = EWMOptions {
+                alpha,
+                ..Default::default()
+            };
+            for result in [s.ewm_mean(options), s.ewm_std(options), s.ewm_var(options)] {
+                assert!(matches!(result, Err(PolarsError::ComputeError(_))));
+            }
+        }
+
+        let s = Series::new("a", &["x", "y"]);
+        let options = EWMOptions::default();
+        for result in [s.ewm_mean(options), s.ewm_std(options), s.ewm_var(options)] {
+            assert!(matches!(result, Err(PolarsError::InvalidOperation(_))));
         }
+
+        let s = Series::new("a", &[true, false]);
+        assert!(s.ewm_mean(options).is_ok());
+    }
+
+    #[test]
+    fn test_ewm_mean_float16() -> PolarsResult<()> {
+        use arrow::types::f16;
+
+        let values = [1.0f32, 2.5, -3.0, 4.0];
+        let arr = PrimitiveArray::from_vec(values.iter().map(|&v| f16::from_f32(v)).collect());
+        let s = Series::try_from(("a", Box::new(arr) as ArrayRef))?;
+        assert_eq!(s.dtype(), &DataType::Float32);
+
+        let out = s.ewm_mean(EWMOptions::default())?;
+        assert_eq!(out.dtype(), &DataType::Float32);
+        let expected = Series::new("a", values).ewm_mean(EWMOptions::default())?;
+        assert!(out.series_equal(&expected));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ewm_keeps_name_and_clears_sorted() -> PolarsResult<()> {
+        let mut s = Series::new("a", &[Some(1i32), None, Some(2), Some(5), Some(9)]);
+        s.set_sorted_flag(IsSorted::Ascending);
+        let mut ca = s.cast(&DataType::Float64)?.f64()?.clone();
+        ca.set_sorted_flag(IsSorted::Ascending);
+
+        let options = EWMOptions::default().and_mask_output_to_input_validity(true);
+        for out in [
+            s.ewm_mean(options)?,
+            s.ewm_std(options)?,
+            s.ewm_var(options)?,
+            ca.ewm_mean(options)?.into_series(),
+            ca.ewm_mean_par(options, 2)?.into_series(),
+        ] {
+            assert_eq!(out.name(), "a");
+            assert_eq!(out.is_sorted_flag(), IsSorted::Not);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_ewm_mean_multiple_chunks() -> PolarsResult<()> {
+        let mut ca = Float64Chunked::new("a", &[Some(1.0), None, Some(4.0)]);
+        ca.append(&Float64Chunked::new("a", &[Some(2.0)]));
+        ca.append(&Float64Chunked::new("a", &[None, Some(8.0), Some(5.0)]));
+        assert_eq!(ca.chunks().len(), 3);
+
+        let options = EWMOptions::default().and_min_periods(2);
+        let out = ca.ewm_mean(options)?;
+        assert_eq!(out.len(), 7);
+        assert_eq!(out.chunks().len(), 1);
+        assert!(out
+            .into_series()
+            .series_equal_missing(&ca.rechunk().ewm_mean(options)?.into_series()));
+        Ok(())
     }
 }
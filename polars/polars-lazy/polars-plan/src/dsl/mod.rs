@@ -1993,43 +1993,40 @@ impl Expr {
         .with_fmt("sample_frac")
     }
 
+    /// Apply an exponentially weighted function to the whole column.
+    ///
+    /// Every output depends on all preceding values in order, so a filter or slice
+    /// must not be pushed below it. `apply` collects the groups
+    /// ([`ApplyOptions::ApplyGroups`]), which the optimizers treat as a pushdown boundary.
     #[cfg(feature = "ewma")]
-    pub fn ewm_mean(self, options: EWMOptions) -> Self {
+    fn apply_ewm<F>(self, function: F, fmt_str: &'static str) -> Self
+    where
+        F: Fn(Series) -> PolarsResult<Series> + 'static + Send + Sync,
+    {
         use DataType::*;
         self.apply(
-            move |s| s.ewm_mean(options),
+            function,
             GetOutput::map_dtype(|dt| match dt {
                 Float64 | Float32 => dt.clone(),
                 _ => Float64,
             }),
         )
-        .with_fmt("ewm_mean")
+        .with_fmt(fmt_str)
+    }
+
+    #[cfg(feature = "ewma")]
+    pub fn ewm_mean(self, options: EWMOptions) -> Self {
+        self.apply_ewm(move |s| s.ewm_mean(options), "ewm_mean")
     }
 
     #[cfg(feature = "ewma")]
     pub fn ewm_std(self, options: EWMOptions) -> Self {
-        use DataType::*;
-        self.apply(
-            move |s| s.ewm_std(options),
-            GetOutput::map_dtype(|dt| match dt {
-                Float64 | Float32 => dt.clone(),
-                _ => Float64,
-            }),
-        )
-        .with_fmt("ewm_std")
+        self.apply_ewm(move |s| s.ewm_std(options), "ewm_std")
     }
 
     #[cfg(feature = "ewma")]
     pub fn ewm_var(self, options: EWMOptions) -> Self {
-        use DataType::*;
-        self.apply(
-            move |s| s.ewm_var(options),
-            GetOutput::map_dtype(|dt| match dt {
-                Float64 | Float32 => dt.clone(),
-                _ => Float64,
-            }),
-        )
-        .with_fmt("ewm_var")
+        self.apply_ewm(move |s| s.ewm_var(options), "ewm_var")
     }
 
     /// Check if any boolean value is `true`
@@ -0,0 +1,82 @@
+use std::sync::Mutex;
+
+use polars_arrow::kernels::ewm::{validate_alpha, EwmMeanState};
+use polars_core::prelude::*;
+
+use crate::prelude::*;
+
+/// An [`AnonymousScan`] that pulls values from an iterator and smooths them with an
+/// exponentially weighted mean while reading.
+///
+/// The scan produces the columns `value` and `ewm_mean`. An iterator can only be
+/// consumed once, so a `LazyFrame` built on it can only be collected once as well.
+pub struct EwmMeanScan<I> {
+    source: Mutex<Option<I>>,
+    options: EWMOptions,
+}
+
+impl<I> EwmMeanScan<I>
+where
+    I: Iterator<Item = Option<f64>> + Send + 'static,
+{
+    pub fn new(source: I, options: EWMOptions) -> Self {
+        Self {
+            source: Mutex::new(Some(source)),
+            options,
+        }
+    }
+
+    /// Create a `LazyFrame` with this scan as its root.
+    pub fn into_lazy(self) -> PolarsResult<LazyFrame> {
+        let args = ScanArgsAnonymous {
+            name: "EWM MEAN SCAN",
+            ..Default::default()
+        };
+        LazyFrame::anonymous_scan(Arc::new(self), args)
+    }
+}
+
+impl<I> AnonymousScan for EwmMeanScan<I>
+where
+    I: Iterator<Item = Option<f64>> + Send,
+{
+    fn scan(&self, scan_opts: AnonymousScanOptions) -> PolarsResult<DataFrame> {
+        let options = self.options;
+        validate_alpha(options.alpha)
+            .map_err(|err| PolarsError::ComputeError(err.to_string().into()))?;
+        let source = self.source.lock().unwrap().take().ok_or_else(|| {
+            PolarsError::ComputeError("the source of an EwmMeanScan was already consumed".into())
+        })?;
+
+        let mut state = EwmMeanState::new(options.alpha, options.adjust, options.min_periods);
+        let (values, means): (Vec<_>, Vec<_>) = source
+            .take(scan_opts.n_rows.unwrap_or(usize::MAX))
+            .map(|opt_x| {
+                let opt_mean = state.update(opt_x);
+                match opt_x {
+                    None if options.mask_output_to_input_validity => (opt_x, None),
+                    _ => (opt_x, opt_mean),
+                }
+            })
+            .unzip();
+
+        DataFrame::new(vec![
+            Series::new("value", values),
+            Series::new("ewm_mean", means),
+        ])
+    }
+
+    fn schema(&self, _infer_schema_length: Option<usize>) -> PolarsResult<Schema> {
+        Ok(Schema::from(
+            [
+                Field::new("value", DataType::Float64),
+                Field::new("ewm_mean", DataType::Float64),
+            ]
+            .into_iter(),
+        ))
+    }
+
+    fn allows_slice_pushdown(&self) -> bool {
+        true
+    }
+}
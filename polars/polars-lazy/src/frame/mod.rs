@@ -11,6 +11,8 @@ mod parquet;
 mod python;
 
 mod anonymous_scan;
+#[cfg(feature = "ewma")]
+mod ewm_scan;
 #[cfg(feature = "pivot")]
 pub mod pivot;
 
@@ -22,6 +24,8 @@ use std::sync::Arc;
 pub use anonymous_scan::*;
 #[cfg(feature = "csv-file")]
 pub use csv::*;
+#[cfg(feature = "ewma")]
+pub use ewm_scan::*;
 #[cfg(feature = "ipc")]
 pub use ipc::*;
 #[cfg(feature = "json")]
@@ -266,3 +266,34 @@ fn test_predicate_on_join_suffix_4788() -> PolarsResult<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "ewma")]
+fn test_ewm_is_pushdown_boundary() -> PolarsResult<()> {
+    let df = df![
+        "a" => [1.0, 5.0, 2.0, 8.0, 3.0, 4.0],
+        "b" => [1, 0, 1, 0, 1, 1]
+    ]?;
+    let options = EWMOptions::default();
+    let lf = df
+        .clone()
+        .lazy()
+        .with_column(col("a").ewm_mean(options).alias("ewm"));
+
+    // filtering first would change the input of the average
+    let q = lf.clone().filter(col("b").eq(lit(1)));
+    assert!(!predicate_at_scan(q.clone()));
+    let expected = df
+        .column("a")?
+        .ewm_mean(options)?
+        .filter(&df.column("b")?.equal(1)?)?;
+    assert!(q.collect()?.column("ewm")?.series_equal(&expected));
+
+    let q = lf.clone().filter(col("ewm").gt(lit(3.0)));
+    assert!(!predicate_at_scan(q));
+
+    let out = lf.slice(2, 3).collect()?;
+    let expected = df.column("a")?.ewm_mean(options)?.slice(2, 3);
+    assert!(out.column("ewm")?.series_equal(&expected));
+    Ok(())
+}
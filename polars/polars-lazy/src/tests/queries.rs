@@ -2011,3 +2011,93 @@ fn test_partitioned_gb_ternary() -> PolarsResult<()> {
 
     Ok(())
 }
+
+#[test]
+#[cfg(feature = "ewma")]
+fn test_ewm_mean_scan() -> PolarsResult<()> {
+    let values = vec![Some(1.0), None, Some(3.0), Some(2.0), Some(6.0)];
+    let options = EWMOptions::default();
+
+    let scan = EwmMeanScan::new(values.clone().into_iter(), options);
+    let lf = scan.into_lazy()?;
+    let out = lf
+        .clone()
+        .filter(col("value").is_not_null())
+        .select([col("ewm_mean")])
+        .collect()?;
+
+    let expected = Series::new("value", &values).ewm_mean(options)?;
+    let expected = expected.filter(&Series::new("", &values).is_not_null())?;
+    assert!(out.column("ewm_mean")?.series_equal(&expected));
+
+    // the source is consumed by the first collect
+    assert!(lf.collect().is_err());
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "ewma")]
+fn test_ewm_mean_expr_reference() -> PolarsResult<()> {
+    // outputs of `ewm_mean` in the Python API, see `test_ewm_mean` in py-polars
+    let reference: [(EWMOptions, &[Option<f64>]); 3] = [
+        (
+            EWMOptions::default().and_adjust(true),
+            &[
+                None,
+                Some(1.0),
+                Some(3.6666666666666665),
+                Some(5.571428571428571),
+                Some(5.571428571428571),
+                Some(3.6666666666666665),
+                Some(4.354838709677419),
+                Some(4.174603174603175),
+            ],
+        ),
+        (
+            EWMOptions::default().and_adjust(false),
+            &[
+                None,
+                Some(1.0),
+                Some(3.0),
+                Some(5.0),
+                Some(5.0),
+                Some(3.5),
+                Some(4.25),
+                Some(4.125),
+            ],
+        ),
+        (
+            EWMOptions::default().and_adjust(true).and_min_periods(3),
+            &[
+                None,
+                None,
+                None,
+                Some(5.571428571428571),
+                Some(5.571428571428571),
+                Some(3.6666666666666665),
+                Some(4.354838709677419),
+                Some(4.174603174603175),
+            ],
+        ),
+    ];
+    let df = df![
+        "a" => [None, Some(1.0), Some(5.0), Some(7.0), None, Some(2.0), Some(5.0), Some(4.0)],
+    ]?;
+
+    for (options, expected) in reference {
+        let out = df
+            .clone()
+            .lazy()
+            .select([col("a").ewm_mean(options)])
+            .collect()?;
+        let out = out.column("a")?.f64()?.into_iter().collect::<Vec<_>>();
+        assert_eq!(out.len(), expected.len());
+        for (a, b) in out.iter().zip(expected) {
+            match (a, b) {
+                (Some(a), Some(b)) => assert!((a - b).abs() < 1e-12, "{a} != {b}"),
+                (a, b) => assert_eq!(a, b),
+            }
+        }
+    }
+    Ok(())
+}
@@ -1622,6 +1622,7 @@ impl PyExpr {
             adjust,
             bias: false,
             min_periods,
+            ..Default::default()
         };
         self.inner.clone().ewm_mean(options).into()
     }
@@ -1631,6 +1632,7 @@ impl PyExpr {
             adjust,
             bias,
             min_periods,
+            ..Default::default()
         };
         self.inner.clone().ewm_std(options).into()
     }
@@ -1640,6 +1642,7 @@ impl PyExpr {
             adjust,
             bias,
             min_periods,
+            ..Default::default()
         };
         self.inner.clone().ewm_var(options).into()
     }